@@ -0,0 +1,471 @@
+use crate::{
+    components::{
+        chorus::Chorus, modulated_delay::ModShape, rotation::ModulatedRotation, AudioContext,
+        Process,
+    },
+    freeverb::Freeverb,
+    gardner::{self, Gardner},
+    mesh::WaveguideMesh,
+    modal::ModalReverb,
+    plate::Plate,
+    rev_tail::{ReverbTail, TailTuning},
+    scattering_fdn::ScatteringFdn,
+    spring::SpringReverb,
+};
+
+/// Duration of the crossfade when switching algorithms, in seconds
+const CROSSFADE: f32 = 0.1;
+/// How long an engine that is not heard anymore keeps running before it is stopped and cleared, in
+/// seconds, so that switching back quickly doesn't cut its tail
+const IDLE_TIME: f32 = 1.0;
+
+/// Late reverb algorithm.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Algorithm {
+    Hall,
+    Plate,
+    Room,
+    Chamber,
+    Spring,
+    Freeverb,
+    GardnerSmall,
+    GardnerMedium,
+    GardnerLarge,
+    Mesh,
+    Modal,
+    Scattering,
+}
+
+impl Algorithm {
+    pub const ALL: [Self; 12] = [
+        Self::Hall,
+        Self::Plate,
+        Self::Room,
+        Self::Chamber,
+        Self::Spring,
+        Self::Freeverb,
+        Self::GardnerSmall,
+        Self::GardnerMedium,
+        Self::GardnerLarge,
+        Self::Mesh,
+        Self::Modal,
+        Self::Scattering,
+    ];
+
+    pub fn from_param(value: f32) -> Self {
+        Self::ALL
+            .get(value.round().max(0.0) as usize)
+            .copied()
+            .unwrap_or(Self::Hall)
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|&a| a == self).unwrap()
+    }
+}
+
+/// Long, sparse tank for a slow build-up
+const HALL: TailTuning = TailTuning {
+    tank: [0.1, 0.35],
+    input_diffusion: &[],
+};
+
+/// Short tank without extra diffusion, keeping some discrete echoes
+const ROOM: TailTuning = TailTuning {
+    tank: [0.03, 0.09],
+    input_diffusion: &[],
+};
+
+/// Medium tank behind a couple of diffusion steps
+const CHAMBER: TailTuning = TailTuning {
+    tank: [0.05, 0.17],
+    input_diffusion: &[0.013, 0.029],
+};
+
+/// Settings each algorithm keeps for itself, so that an algorithm comes back with its own settings
+/// when selected again.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TailSettings {
+    pub size: f32,
+    pub decay: f32,
+    /// Damping amount, see `damping_cutoff`
+    pub damping: f32,
+}
+
+impl Default for TailSettings {
+    fn default() -> Self {
+        Self {
+            size: 0.5,
+            decay: 0.5,
+            damping: 0.0,
+        }
+    }
+}
+
+/// Cutoff of the damping filters inside the feedback paths, from no damping at 0 down to 200 Hz
+/// at 1.
+pub fn damping_cutoff(damping: f32) -> f32 {
+    if damping <= 0.0 {
+        f32::INFINITY
+    } else {
+        200.0 * 100f32.powf(1.0 - damping.min(1.0))
+    }
+}
+
+/// Late reverb network of one algorithm.
+enum Engine<const N: usize> {
+    Fdn(ReverbTail<N, ModulatedRotation<N>>),
+    // Boxed, its tank being much larger than the other engines
+    Plate(Box<Plate<N>>),
+    Spring(SpringReverb<N>),
+    Freeverb(Freeverb<N>),
+    Gardner(Gardner<N>),
+    Mesh(WaveguideMesh<N>),
+    Modal(ModalReverb<N>),
+    Scattering(ScatteringFdn<N>),
+}
+
+impl<const N: usize> Engine<N> {
+    fn new(algorithm: Algorithm, samplerate: f32) -> Self {
+        match algorithm {
+            Algorithm::Hall => Self::fdn(samplerate, &HALL),
+            Algorithm::Plate => Self::Plate(Box::new(Plate::new(samplerate))),
+            Algorithm::Room => Self::fdn(samplerate, &ROOM),
+            Algorithm::Chamber => Self::fdn(samplerate, &CHAMBER),
+            Algorithm::Spring => Self::Spring(SpringReverb::new(samplerate)),
+            Algorithm::Freeverb => Self::Freeverb(Freeverb::new(samplerate)),
+            Algorithm::GardnerSmall => Self::Gardner(Gardner::new(samplerate, &gardner::SMALL)),
+            Algorithm::GardnerMedium => Self::Gardner(Gardner::new(samplerate, &gardner::MEDIUM)),
+            Algorithm::GardnerLarge => Self::Gardner(Gardner::new(samplerate, &gardner::LARGE)),
+            Algorithm::Mesh => Self::Mesh(WaveguideMesh::new(samplerate)),
+            Algorithm::Modal => Self::Modal(ModalReverb::new(samplerate)),
+            Algorithm::Scattering => Self::Scattering(ScatteringFdn::new(samplerate)),
+        }
+    }
+
+    fn fdn(samplerate: f32, tuning: &TailTuning) -> Self {
+        Self::Fdn(ReverbTail::new(samplerate, tuning, ModulatedRotation::new))
+    }
+
+    /// Applies the changes that restructure the engine, which are too costly or too abrupt to be
+    /// done every sample.
+    fn update_block(&mut self) {
+        match self {
+            Self::Mesh(mesh) => mesh.update_block(),
+            Self::Modal(modal) => modal.update_block(),
+            _ => {}
+        }
+    }
+
+    fn update_size(&mut self, size: f32) {
+        match self {
+            Self::Fdn(tail) => tail.update_size(size),
+            Self::Plate(plate) => plate.update_size(size),
+            Self::Spring(spring) => spring.update_size(size),
+            Self::Freeverb(freeverb) => freeverb.update_size(size),
+            Self::Gardner(gardner) => gardner.update_size(size),
+            Self::Mesh(mesh) => mesh.update_size(size),
+            Self::Modal(modal) => modal.update_size(size),
+            Self::Scattering(fdn) => fdn.update_size(size),
+        }
+    }
+
+    fn update_feedback(&mut self, feedback: f32) {
+        match self {
+            Self::Fdn(tail) => tail.update_feedback(feedback),
+            Self::Plate(plate) => plate.update_feedback(feedback),
+            Self::Spring(spring) => spring.update_feedback(feedback),
+            Self::Freeverb(freeverb) => freeverb.update_feedback(feedback),
+            Self::Gardner(gardner) => gardner.update_feedback(feedback),
+            Self::Mesh(mesh) => mesh.update_feedback(feedback),
+            Self::Modal(modal) => modal.update_feedback(feedback),
+            Self::Scattering(fdn) => fdn.update_feedback(feedback),
+        }
+    }
+
+    fn update_damping(&mut self, samplerate: f32, cutoff: f32) {
+        match self {
+            Self::Fdn(tail) => tail.update_damping(samplerate, cutoff),
+            Self::Plate(plate) => plate.update_damping(cutoff),
+            Self::Spring(spring) => spring.update_damping(cutoff),
+            Self::Freeverb(freeverb) => freeverb.update_damping(cutoff),
+            Self::Gardner(gardner) => gardner.update_damping(cutoff),
+            Self::Mesh(mesh) => mesh.update_damping(cutoff),
+            Self::Modal(modal) => modal.update_damping(cutoff),
+            Self::Scattering(fdn) => fdn.update_damping(cutoff),
+        }
+    }
+
+    fn update_tension(&mut self, tension: f32) {
+        if let Self::Spring(spring) = self {
+            spring.update_tension(tension);
+        }
+    }
+
+    fn update_decorrelation(&mut self, decorrelation: f32) {
+        match self {
+            Self::Plate(plate) => plate.update_decorrelation(decorrelation),
+            Self::Spring(spring) => spring.update_decorrelation(decorrelation),
+            Self::Freeverb(freeverb) => freeverb.update_decorrelation(decorrelation),
+            Self::Gardner(gardner) => gardner.update_decorrelation(decorrelation),
+            Self::Mesh(mesh) => mesh.update_decorrelation(decorrelation),
+            Self::Modal(modal) => modal.update_decorrelation(decorrelation),
+            _ => {}
+        }
+    }
+
+    fn update_matrix_modulation(&mut self, rate: f32, depth: f32) {
+        if let Self::Fdn(tail) = self {
+            tail.update_matrix_modulation(rate, depth);
+        }
+    }
+
+    fn update_tank_modulation(&mut self, shape: ModShape, rate: f32, depth: f32) {
+        if let Self::Fdn(tail) = self {
+            tail.update_tank_modulation(shape, rate, depth);
+        }
+    }
+
+    fn update_chorus(&mut self, update: impl FnMut(&mut Chorus<f32>)) {
+        // The plate modulates its own tank allpasses, the others have no chorus
+        if let Self::Fdn(tail) = self {
+            tail.update_chorus(update);
+        }
+    }
+
+    fn update_chorus_drywet(&mut self, dw: f32) {
+        if let Self::Fdn(tail) = self {
+            tail.update_chorus_drywet(dw);
+        }
+    }
+
+    fn process(&mut self, ctx: &AudioContext, input_frame: &[f32], output_frame: &mut [f32]) {
+        match self {
+            Self::Fdn(tail) => tail.process(ctx, input_frame, output_frame),
+            Self::Plate(plate) => plate.process(ctx, input_frame, output_frame),
+            Self::Spring(spring) => spring.process(ctx, input_frame, output_frame),
+            Self::Freeverb(freeverb) => freeverb.process(ctx, input_frame, output_frame),
+            Self::Gardner(gardner) => gardner.process(ctx, input_frame, output_frame),
+            Self::Mesh(mesh) => mesh.process(ctx, input_frame, output_frame),
+            Self::Modal(modal) => modal.process(ctx, input_frame, output_frame),
+            Self::Scattering(fdn) => fdn.process(ctx, input_frame, output_frame),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            Self::Fdn(tail) => tail.reset(),
+            Self::Plate(plate) => plate.reset(),
+            Self::Spring(spring) => spring.reset(),
+            Self::Freeverb(freeverb) => freeverb.reset(),
+            Self::Gardner(gardner) => gardner.reset(),
+            Self::Mesh(mesh) => mesh.reset(),
+            Self::Modal(modal) => modal.reset(),
+            Self::Scattering(fdn) => fdn.reset(),
+        }
+    }
+}
+
+/// All the late reverb algorithms, of which one is heard at a time, each with its own
+/// `TailSettings`. Switching algorithms crossfades between the outgoing and incoming engines; each
+/// engine fades from wherever it is, so switching again during a fade never cuts off an engine.
+///
+/// Engines that are not heard anymore get no input, and are stopped and cleared once they have been
+/// faded out for `IDLE_TIME`, whatever is left inside them, so that even lossless settings don't
+/// keep them running and selecting them again doesn't bring back an old tail.
+pub(crate) struct Algorithms<const N: usize> {
+    sample_rate: f32,
+    engines: Vec<Engine<N>>,
+    running: Vec<bool>,
+    /// Samples each engine has spent unheard
+    idle: Vec<usize>,
+    /// Output gain of each engine, ramping towards 1 for the current one and 0 for the others
+    gains: Vec<f32>,
+    /// Last settings of each algorithm, which stopped engines catch up with when started again
+    settings: Vec<TailSettings>,
+    current: Algorithm,
+}
+
+impl<const N: usize> Algorithms<N> {
+    pub fn new(samplerate: f32, algorithm: Algorithm) -> Self {
+        Self {
+            sample_rate: samplerate,
+            engines: Algorithm::ALL
+                .iter()
+                .map(|&a| Engine::new(a, samplerate))
+                .collect(),
+            running: Algorithm::ALL.iter().map(|&a| a == algorithm).collect(),
+            idle: vec![0; Algorithm::ALL.len()],
+            gains: Algorithm::ALL
+                .iter()
+                .map(|&a| if a == algorithm { 1.0 } else { 0.0 })
+                .collect(),
+            settings: vec![TailSettings::default(); Algorithm::ALL.len()],
+            current: algorithm,
+        }
+    }
+
+    pub fn set_algorithm(&mut self, algorithm: Algorithm) {
+        if algorithm == self.current {
+            return;
+        }
+        self.current = algorithm;
+        let i = algorithm.index();
+        if !self.running[i] {
+            // Stopped engines aren't updated, so they catch up before being heard again
+            let settings = self.settings[i];
+            let engine = &mut self.engines[i];
+            engine.update_size(settings.size);
+            engine.update_feedback(settings.decay);
+            engine.update_damping(self.sample_rate, damping_cutoff(settings.damping));
+            engine.update_block();
+        }
+        self.running[i] = true;
+        self.idle[i] = 0;
+    }
+
+    /// Applies the structural changes of the running engines, once per block.
+    pub fn update_block(&mut self) {
+        for (engine, &running) in self.engines.iter_mut().zip(self.running.iter()) {
+            if running {
+                engine.update_block();
+            }
+        }
+    }
+
+    /// Sets the settings of every algorithm, in the order of `Algorithm::ALL`. Only running
+    /// engines are updated, the others catching up when they are started again.
+    pub fn update_settings(&mut self, settings: &[TailSettings]) {
+        for (i, (engine, s)) in self.engines.iter_mut().zip(settings.iter()).enumerate() {
+            let previous = std::mem::replace(&mut self.settings[i], *s);
+            if !self.running[i] {
+                continue;
+            }
+            engine.update_size(s.size);
+            engine.update_feedback(s.decay);
+            if s.damping != previous.damping {
+                engine.update_damping(self.sample_rate, damping_cutoff(s.damping));
+            }
+        }
+    }
+
+    pub fn update_tension(&mut self, tension: f32) {
+        self.engines
+            .iter_mut()
+            .for_each(|e| e.update_tension(tension));
+    }
+
+    /// Sets the decorrelation of the plugin mixers, which the engines with a stereo core follow.
+    pub fn update_decorrelation(&mut self, decorrelation: f32) {
+        self.engines
+            .iter_mut()
+            .for_each(|e| e.update_decorrelation(decorrelation));
+    }
+
+    /// Sets the rate (in Hz) and depth of the feedback matrix modulation of the FDN engines.
+    pub fn update_matrix_modulation(&mut self, rate: f32, depth: f32) {
+        self.engines
+            .iter_mut()
+            .for_each(|e| e.update_matrix_modulation(rate, depth));
+    }
+
+    /// Sets the shape, rate (in Hz) and depth (in seconds) of the delay modulation inside the
+    /// feedback loop of the FDN engines.
+    pub fn update_tank_modulation(&mut self, shape: ModShape, rate: f32, depth: f32) {
+        self.engines
+            .iter_mut()
+            .for_each(|e| e.update_tank_modulation(shape, rate, depth));
+    }
+
+    pub fn update_chorus(&mut self, mut update: impl FnMut(&mut Chorus<f32>)) {
+        self.engines
+            .iter_mut()
+            .for_each(|e| e.update_chorus(&mut update));
+    }
+
+    pub fn update_chorus_drywet(&mut self, dw: f32) {
+        self.engines
+            .iter_mut()
+            .for_each(|e| e.update_chorus_drywet(dw));
+    }
+}
+
+impl<const N: usize> Process for Algorithms<N> {
+    type T = f32;
+    const NIN: usize = N;
+    const NOUT: usize = N;
+
+    fn process(&mut self, ctx: &AudioContext, input_frame: &[f32], output_frame: &mut [f32]) {
+        let silence = [0.0; N];
+        let current = self.current.index();
+        let step = 1.0 / (CROSSFADE * self.sample_rate);
+        let idle_samples = (IDLE_TIME * self.sample_rate) as usize;
+        output_frame.iter_mut().for_each(|x| *x = 0.0);
+
+        for (i, engine) in self.engines.iter_mut().enumerate() {
+            if !self.running[i] {
+                continue;
+            }
+            let gain = &mut self.gains[i];
+            *gain = if i == current {
+                (*gain + step).min(1.0)
+            } else {
+                (*gain - step).max(0.0)
+            };
+            let mut out = [0.0; N];
+            engine.process(
+                ctx,
+                if i == current { input_frame } else { &silence },
+                &mut out,
+            );
+            for (o, x) in output_frame.iter_mut().zip(out.iter()) {
+                *o += x * *gain;
+            }
+            if i != current && *gain == 0.0 {
+                // Not heard anymore, so it's only kept running in case it's selected again
+                self.idle[i] += 1;
+                if self.idle[i] > idle_samples {
+                    engine.reset();
+                    self.running[i] = false;
+                }
+            } else {
+                self.idle[i] = 0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unheard_engines_stop_and_clear_even_when_lossless() {
+        let sample_rate = 48000.0;
+        let ctx = AudioContext::new(sample_rate);
+        let mut algorithms = Algorithms::<8>::new(sample_rate, Algorithm::Hall);
+        let lossless = TailSettings {
+            size: 0.5,
+            decay: 1.0,
+            damping: 0.0,
+        };
+        algorithms.update_settings(&[lossless; 12]);
+        let mut output = [0.0; 8];
+        for i in 0..4800 {
+            algorithms.process(&ctx, &[(i as f32 * 0.1).sin(); 8], &mut output);
+        }
+
+        algorithms.set_algorithm(Algorithm::Plate);
+        let samples = ((CROSSFADE + IDLE_TIME) * sample_rate) as usize + 10;
+        for _ in 0..samples {
+            algorithms.process(&ctx, &[0.0; 8], &mut output);
+        }
+        assert!(!algorithms.running[Algorithm::Hall.index()]);
+
+        // Selecting the stopped engine again starts it from silence
+        algorithms.set_algorithm(Algorithm::Hall);
+        let mut hall = [0.0; 8];
+        algorithms.engines[Algorithm::Hall.index()].process(&ctx, &[0.0; 8], &mut hall);
+        assert!(hall.iter().all(|x| *x == 0.0));
+    }
+}
@@ -1,27 +1,40 @@
 use super::{delay::Delay, hadamard::Hadamard, parallel::Parallel, Process};
 
-pub struct Allpass<const N: usize> {
+/// Multichannel diffuser: a set of delays of increasing length followed by a Hadamard mix, or any
+/// other orthogonal mix `M`. It is lossless like an allpass, but doesn't filter each channel; see
+/// `SchroederAllpass` for a true allpass filter.
+pub struct Allpass<const N: usize, M = Hadamard<f32, N>> {
     parallel: Parallel<Delay<f32>, N>,
-    hadamard: Hadamard<f32, N>,
+    hadamard: M,
 }
 
 impl<const N: usize> Allpass<N> {
     pub fn new(max_samples: usize) -> Self {
+        Self::with_mixer(max_samples, Hadamard::new())
+    }
+}
+
+impl<const N: usize, M> Allpass<N, M> {
+    pub fn with_mixer(max_samples: usize, mixer: M) -> Self {
         Self {
             parallel: Parallel::new(|i| {
                 Delay::new((((i as f32 + 1.0) / N as f32).powi(2) * max_samples as f32) as _)
             }),
-            hadamard: Hadamard::new(),
+            hadamard: mixer,
         }
     }
 
+    pub fn mixer_mut(&mut self) -> &mut M {
+        &mut self.hadamard
+    }
+
     pub fn update(&mut self, fac: f32) {
         self.parallel
             .update(|d| d.pos = fac);
     }
 }
 
-impl<const N: usize> Process for Allpass<N> {
+impl<const N: usize, M: Process<T = f32>> Process for Allpass<N, M> {
     type T = f32;
     const NIN: usize = N;
     const NOUT: usize = N;
@@ -38,4 +51,9 @@ impl<const N: usize> Process for Allpass<N> {
         self.parallel.process(ctx, input_frame, &mut ptemp);
         self.hadamard.process(ctx, &ptemp, output_frame);
     }
+
+    fn reset(&mut self) {
+        self.parallel.reset();
+        self.hadamard.reset();
+    }
 }
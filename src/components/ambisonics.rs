@@ -0,0 +1,105 @@
+use nalgebra::{SMatrix, SVectorSlice, SVectorSliceMut};
+
+use super::{AudioContext, Process};
+
+/// Number of ambisonic channels for the given order.
+pub const fn channel_count(order: usize) -> usize {
+    (order + 1) * (order + 1)
+}
+
+/// Real spherical harmonics up to 3rd order, in ACN order with SN3D normalization (AmbiX).
+/// Azimuth is counter-clockwise from the front, elevation is upwards, both in radians.
+pub fn sn3d(azimuth: f32, elevation: f32) -> [f32; 16] {
+    let x = elevation.cos() * azimuth.cos();
+    let y = elevation.cos() * azimuth.sin();
+    let z = elevation.sin();
+    let sqrt3 = 3f32.sqrt();
+    [
+        1.0,
+        y,
+        z,
+        x,
+        sqrt3 * x * y,
+        sqrt3 * y * z,
+        (3.0 * z * z - 1.0) / 2.0,
+        sqrt3 * x * z,
+        sqrt3 / 2.0 * (x * x - y * y),
+        (5.0f32 / 8.0).sqrt() * y * (3.0 * x * x - y * y),
+        15f32.sqrt() * x * y * z,
+        (3.0f32 / 8.0).sqrt() * y * (5.0 * z * z - 1.0),
+        z * (5.0 * z * z - 3.0) / 2.0,
+        (3.0f32 / 8.0).sqrt() * x * (5.0 * z * z - 1.0),
+        15f32.sqrt() / 2.0 * z * (x * x - y * y),
+        (5.0f32 / 8.0).sqrt() * x * (x * x - 3.0 * y * y),
+    ]
+}
+
+/// Directions of the vertices of a cube, as (azimuth, elevation) pairs. Used to spread 8
+/// decorrelated lines evenly around the listener.
+///
+/// Even indices are on the left and odd indices on the right, then front before back and upper
+/// before lower, so that the plain routing of `Mixer` sends each line to the matching speaker.
+pub fn cube_directions() -> [(f32, f32); 8] {
+    let elevation = (1.0 / 3f32.sqrt()).asin();
+    let mut directions = [(0.0, 0.0); 8];
+    for (i, dir) in directions.iter_mut().enumerate() {
+        let side = if i % 2 == 0 { 1.0 } else { -1.0 };
+        let azimuth = if (i / 2) % 2 == 0 { 45f32 } else { 135f32 }.to_radians() * side;
+        *dir = (azimuth, if i < 4 { elevation } else { -elevation });
+    }
+    directions
+}
+
+/// Encodes N mono sources placed at fixed directions into C ambisonic channels (AmbiX ordering
+/// and normalization).
+pub struct AmbisonicEncoder<const N: usize, const C: usize> {
+    transfer: SMatrix<f32, C, N>,
+}
+
+impl<const N: usize, const C: usize> AmbisonicEncoder<N, C> {
+    pub fn new(directions: &[(f32, f32)]) -> Self {
+        assert_eq!(directions.len(), N);
+        assert!(C <= channel_count(3));
+        let gain = (N as f32).sqrt().recip();
+        Self {
+            transfer: SMatrix::from_fn(|c, i| {
+                let (azimuth, elevation) = directions[i];
+                sn3d(azimuth, elevation)[c] * gain
+            }),
+        }
+    }
+}
+
+impl<const N: usize, const C: usize> Process for AmbisonicEncoder<N, C> {
+    type T = f32;
+    const NIN: usize = N;
+    const NOUT: usize = C;
+
+    #[inline(always)]
+    fn process(&mut self, _: &AudioContext, inputs: &[f32], outputs: &mut [f32]) {
+        let invec = SVectorSlice::<f32, N>::from_slice(inputs);
+        let mut outvec = SVectorSliceMut::<f32, C>::from_slice(outputs);
+        self.transfer.mul_to(&invec, &mut outvec);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sn3d_front() {
+        let sh = sn3d(0.0, 0.0);
+        assert_eq!(&sh[0..4], &[1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_sn3d_first_order_unit_norm() {
+        // With SN3D, the first order components have the same norm as W in every direction
+        for &(az, el) in &[(0.3, 0.2), (2.0, -1.0), (-1.2, 0.7)] {
+            let sh = sn3d(az, el);
+            let norm = sh[1] * sh[1] + sh[2] * sh[2] + sh[3] * sh[3];
+            assert!((norm - 1.0).abs() < 1e-6);
+        }
+    }
+}
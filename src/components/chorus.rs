@@ -2,7 +2,11 @@ use audio::Sample;
 use num_traits::{Float, FloatConst, FromPrimitive};
 use std::fmt::Debug;
 
-use super::{delay::Delay, ext::DurationFloatExt, SingleChannelProcess};
+use super::{
+    delay::Delay,
+    lfo::{Lfo, LfoRate, LfoShape},
+    SingleChannelProcess,
+};
 
 #[derive(Copy, Clone, Debug)]
 pub struct Hz<T>(T);
@@ -33,40 +37,55 @@ impl<T: Float> Hz<T> {
     }
 }
 
+/// Modulated delay whose length swings around `max_depth` by up to `max_depth` either way.
 pub struct Chorus<T> {
     amplitude: T,
-    freq: Hz<T>,
-    pos: T,
+    max_depth: T,
+    lfo: Lfo,
     delay: Delay<T>,
 }
 
-impl<T> Chorus<T> {
-    pub fn set_amplitude(&mut self, t: T) {
-        self.amplitude = t
+impl<T: Float> Chorus<T> {
+    /// Sets the swing of the delay in seconds, up to the max depth.
+    pub fn set_depth(&mut self, depth: T) {
+        self.amplitude = (depth / self.max_depth).max(T::zero()).min(T::one());
     }
 
     pub fn set_frequency(&mut self, freq: impl Into<Hz<T>>) {
-        self.freq = freq.into();
+        let freq = freq.into().to_freq().to_f32().unwrap_or(0.0);
+        self.lfo.rate = LfoRate::Hz(freq);
+    }
+
+    /// Syncs the modulation to the host tempo, one cycle lasting `beats` beats.
+    pub fn set_sync(&mut self, beats: f32) {
+        self.lfo.rate = LfoRate::Beats(beats);
+    }
+
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.lfo.shape = shape;
     }
 
     pub fn set_pos(&mut self, p: T) {
-        self.pos = p;
+        self.lfo.set_phase(p.to_f32().unwrap_or(0.0));
     }
 }
 
 impl<T: Debug + Sample + Float + FromPrimitive> Chorus<T> {
-    pub fn new(max_delay: usize) -> Self {
+    /// Creates a chorus able to swing by `max_depth` seconds, sizing its buffer accordingly.
+    pub fn new(sample_rate: T, max_depth: T) -> Self {
+        let two = T::one() + T::one();
+        let len = (two * max_depth * sample_rate)
+            .ceil()
+            .to_usize()
+            .unwrap_or(0)
+            + 2;
         Self {
             amplitude: T::zero(),
-            freq: Hz::from_frequency(T::one()),
-            pos: T::zero(),
-            delay: Delay::new(max_delay),
+            max_depth,
+            lfo: Lfo::new(LfoShape::Sine, LfoRate::Hz(1.0), 0.0, 0),
+            delay: Delay::new(len),
         }
     }
-
-    fn tick(&mut self, ctx: &super::AudioContext) {
-        self.pos = self.pos + ctx.tick_length().as_seconds::<T>() * self.freq.to_freq();
-    }
 }
 
 impl<T: Debug + Sample + Float + FloatConst + FromPrimitive> SingleChannelProcess for Chorus<T> {
@@ -75,8 +94,12 @@ impl<T: Debug + Sample + Float + FloatConst + FromPrimitive> SingleChannelProces
     fn process_single_channel(&mut self, ctx: &super::AudioContext, value: Self::T) -> Self::T {
         let two = T::one().add(T::one());
         let half = two.recip();
-        self.delay.pos = self.pos.fract().mul(T::TAU()).sin() * self.amplitude / two + half;
-        self.tick(ctx);
+        let lfo = T::from_f32(self.lfo.next(ctx)).unwrap();
+        self.delay.pos = lfo * self.amplitude / two + half;
         self.delay.process_single_channel(ctx, value)
     }
+
+    fn reset_single_channel(&mut self) {
+        self.delay.reset_single_channel();
+    }
 }
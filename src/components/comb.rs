@@ -0,0 +1,69 @@
+use audio::Sample;
+use num_traits::{Float, FloatConst, FromPrimitive};
+
+use super::{delay::DelayLine, filter::OnePole, AudioContext, SingleChannelProcess};
+
+/// Lowpass-feedback comb filter, as in Moorer's reverberator and Freeverb: the output of the delay
+/// line goes through a one-pole lowpass before being fed back, so that high frequencies decay
+/// faster than low ones.
+pub struct DampedComb<T> {
+    pub feedback: T,
+    delay: T,
+    line: DelayLine<T>,
+    damping: OnePole<T>,
+}
+
+impl<T: Sample + Float + FloatConst + FromPrimitive> DampedComb<T> {
+    pub fn new(max_delay: usize, delay: T, feedback: T) -> Self {
+        Self {
+            feedback,
+            delay,
+            line: DelayLine::new(max_delay.max(2)),
+            damping: OnePole::bypass(),
+        }
+    }
+
+    /// Sets the delay in samples.
+    pub fn set_delay(&mut self, delay: T) {
+        self.delay = delay;
+    }
+
+    pub fn set_damping(&mut self, samplerate: T, cutoff: T) {
+        self.damping.set_cutoff(samplerate, cutoff);
+    }
+}
+
+impl<T: Sample + Float + FloatConst + FromPrimitive> SingleChannelProcess for DampedComb<T> {
+    type T = T;
+
+    #[inline(always)]
+    fn process_single_channel(&mut self, ctx: &AudioContext, value: T) -> T {
+        let delayed = self.line.read(self.delay.max(T::one()) - T::one());
+        let damped = self.damping.process_single_channel(ctx, delayed);
+        self.line.push_pop(value + self.feedback * damped);
+        delayed
+    }
+
+    fn reset_single_channel(&mut self) {
+        self.line.clear();
+        self.damping.reset_single_channel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_echoes_decay_by_feedback() {
+        let ctx = AudioContext::new(48000.0);
+        let mut comb = DampedComb::new(16, 10.0, 0.5);
+        let out: Vec<f32> = (0..31)
+            .map(|i| comb.process_single_channel(&ctx, if i == 0 { 1.0 } else { 0.0 }))
+            .collect();
+        assert_eq!(out[10], 1.0);
+        assert_eq!(out[20], 0.5);
+        assert_eq!(out[30], 0.25);
+        assert_eq!(out.iter().filter(|x| **x != 0.0).count(), 3);
+    }
+}
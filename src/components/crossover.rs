@@ -0,0 +1,190 @@
+use audio::Sample;
+use num_traits::{Float, FloatConst};
+
+use super::{filter::Biquad, AudioContext, Process, SingleChannelProcess};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Slope {
+    /// 2nd order Linkwitz-Riley, 12 dB/oct
+    Db12,
+    /// 4th order Linkwitz-Riley, 24 dB/oct
+    Db24,
+}
+
+impl Slope {
+    pub fn from_param(value: f32) -> Self {
+        if value < 0.5 {
+            Self::Db12
+        } else {
+            Self::Db24
+        }
+    }
+}
+
+/// Linkwitz-Riley crossover splitting one channel into low and high bands (in that order), which
+/// sum back to an allpass response.
+pub struct Crossover<T> {
+    slope: Slope,
+    lowpass: [Biquad<T>; 2],
+    highpass: [Biquad<T>; 2],
+}
+
+impl<T: Float + FloatConst> Crossover<T> {
+    pub fn new(samplerate: T, freq: T, slope: Slope) -> Self {
+        let mut crossover = Self {
+            slope,
+            lowpass: [
+                Biquad::lowpass(samplerate, freq, T::one()),
+                Biquad::lowpass(samplerate, freq, T::one()),
+            ],
+            highpass: [
+                Biquad::highpass(samplerate, freq, T::one()),
+                Biquad::highpass(samplerate, freq, T::one()),
+            ],
+        };
+        crossover.set(samplerate, freq, slope);
+        crossover
+    }
+
+    pub fn set(&mut self, samplerate: T, freq: T, slope: Slope) {
+        self.slope = slope;
+        // LR2 is a single critically damped section, LR4 is two cascaded Butterworth sections
+        let q = match slope {
+            Slope::Db12 => T::one() / (T::one() + T::one()),
+            Slope::Db24 => T::FRAC_1_SQRT_2(),
+        };
+        for f in &mut self.lowpass {
+            f.set_lowpass(samplerate, freq, q);
+        }
+        for f in &mut self.highpass {
+            f.set_highpass(samplerate, freq, q);
+        }
+    }
+}
+
+impl<T: Sample + Float> Process for Crossover<T> {
+    type T = T;
+    const NIN: usize = 1;
+    const NOUT: usize = 2;
+
+    #[inline(always)]
+    fn process(&mut self, ctx: &AudioContext, input_frame: &[T], output_frame: &mut [T]) {
+        let x = input_frame[0];
+        let low = self.lowpass[0].process_single_channel(ctx, x);
+        let high = self.highpass[0].process_single_channel(ctx, x);
+        match self.slope {
+            Slope::Db12 => {
+                output_frame[0] = low;
+                // LR2 bands are in phase opposition at the crossover frequency
+                output_frame[1] = -high;
+            }
+            Slope::Db24 => {
+                output_frame[0] = self.lowpass[1].process_single_channel(ctx, low);
+                output_frame[1] = self.highpass[1].process_single_channel(ctx, high);
+            }
+        }
+    }
+}
+
+/// Folds the low end of a multichannel signal to mono below the crossover frequency. The `lfe`
+/// channel, if any, is passed through and left out of the mono low end.
+pub struct MonoBass<T, const N: usize> {
+    samplerate: T,
+    freq: T,
+    slope: Slope,
+    lfe: Option<usize>,
+    crossovers: Vec<Crossover<T>>,
+}
+
+impl<T: Float + FloatConst, const N: usize> MonoBass<T, N> {
+    pub fn new(samplerate: T, freq: T, slope: Slope, lfe: Option<usize>) -> Self {
+        Self {
+            samplerate,
+            freq,
+            slope,
+            lfe,
+            crossovers: (0..N)
+                .map(|_| Crossover::new(samplerate, freq, slope))
+                .collect(),
+        }
+    }
+
+    pub fn set(&mut self, freq: T, slope: Slope) {
+        if freq != self.freq || slope != self.slope {
+            self.freq = freq;
+            self.slope = slope;
+            for c in &mut self.crossovers {
+                c.set(self.samplerate, freq, slope);
+            }
+        }
+    }
+}
+
+impl<T: Sample + Float, const N: usize> Process for MonoBass<T, N> {
+    type T = T;
+    const NIN: usize = N;
+    const NOUT: usize = N;
+
+    #[inline(always)]
+    fn process(&mut self, ctx: &AudioContext, input_frame: &[T], output_frame: &mut [T]) {
+        let mut bands = [[T::ZERO; 2]; N];
+        for (i, band) in bands.iter_mut().enumerate() {
+            self.crossovers[i].process(ctx, &input_frame[i..i + 1], band);
+        }
+        let folded = N - self.lfe.map_or(0, |_| 1);
+        let low = bands
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| Some(i) != self.lfe)
+            .fold(T::ZERO, |acc, (_, b)| acc + b[0])
+            / T::from(folded.max(1)).unwrap();
+        for (i, (out, band)) in output_frame.iter_mut().zip(bands.iter()).enumerate() {
+            *out = if Some(i) == self.lfe {
+                input_frame[i]
+            } else {
+                low + band[1]
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn band_sum_energy(slope: Slope) -> f32 {
+        let ctx = AudioContext::new(48000.0);
+        let mut crossover = Crossover::new(48000.0, 200.0, slope);
+        let mut out = [0.0; 2];
+        (0..48000)
+            .map(|i| {
+                let x = if i == 0 { 1.0 } else { 0.0 };
+                crossover.process(&ctx, &[x], &mut out);
+                (out[0] + out[1]).powi(2)
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_lr2_sums_to_allpass() {
+        assert!((band_sum_energy(Slope::Db12) - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_lr4_sums_to_allpass() {
+        assert!((band_sum_energy(Slope::Db24) - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_mono_bass_skips_lfe() {
+        let ctx = AudioContext::new(48000.0);
+        let mut mono_bass = MonoBass::<f32, 3>::new(48000.0, 200.0, Slope::Db24, Some(1));
+        let mut out = [0.0; 3];
+        for _ in 0..48000 {
+            mono_bass.process(&ctx, &[0.0, 1.0, 0.0], &mut out);
+        }
+        // Only the LFE carries signal, and none of it leaks into the other channels
+        assert_eq!(out[1], 1.0);
+        assert!(out[0].abs() < 1e-6 && out[2].abs() < 1e-6);
+    }
+}
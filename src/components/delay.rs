@@ -27,6 +27,27 @@ impl<T: Sample> DelayLine<T> {
         self.data.push_back(val);
         old
     }
+
+    /// Fills the line with silence, keeping its length.
+    pub fn clear(&mut self) {
+        self.data.iter_mut().for_each(|x| *x = T::ZERO);
+    }
+}
+
+impl<T: Sample + Float + FromPrimitive> DelayLine<T> {
+    /// Reads the line `delay` samples in the past, with linear interpolation. A delay of zero
+    /// returns the last pushed value.
+    pub fn read(&self, delay: T) -> T {
+        let len = self.data.len();
+        let max = T::from_usize(len - 1).unwrap();
+        let delay = delay.max(T::ZERO).min(max);
+        let offset = delay.floor().to_usize().unwrap();
+        let fract = delay.fract();
+
+        let x0 = self.data[len - 1 - offset];
+        let x1 = self.data.get(len.wrapping_sub(2 + offset)).copied().unwrap_or(x0);
+        x0 + fract * (x1 - x0)
+    }
 }
 
 impl<T> Deref for DelayLine<T> {
@@ -86,6 +107,10 @@ impl<T: Sample + Float + FromPrimitive> SingleChannelProcess for Delay<T> {
         self.delay.push_pop(value);
         self.interpolate(self.pos)
     }
+
+    fn reset_single_channel(&mut self) {
+        self.delay.clear();
+    }
 }
 
 pub struct FeedbackDelay<T> {
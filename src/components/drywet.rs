@@ -43,4 +43,8 @@ where
                 input_frame[i] * (P::T::one() - self.amount) + result[i] * self.amount;
         }
     }
+
+    fn reset(&mut self) {
+        self.process.reset();
+    }
 }
@@ -0,0 +1,226 @@
+use std::f32::consts::FRAC_PI_4;
+
+use super::{
+    chorus::{Chorus, Hz},
+    AudioContext, Process, SingleChannelProcess,
+};
+
+/// Largest number of voices of an `Ensemble`
+pub const MAX_VOICES: usize = 8;
+/// Smallest number of voices of an `Ensemble`
+pub const MIN_VOICES: usize = 3;
+/// Longest swing of the voices, in seconds
+pub const MAX_DEPTH: f32 = 0.01;
+/// Spread of the voice rates around the base rate, the outer voices being this much faster or
+/// slower
+const DETUNE: f32 = 0.15;
+/// Time for a voice to fade in or out when the number of voices changes, in seconds
+const FADE: f32 = 0.05;
+
+/// One voice of the ensemble, with a chorus for each channel.
+struct Voice {
+    choruses: [Chorus<f32>; 2],
+    /// Constant-power gains of the voice on each channel
+    pan: [f32; 2],
+    /// Current level, ramping towards 1 while the voice is used and 0 otherwise
+    gain: f32,
+    /// Samples fed since the voice was started; its delay lines hold stale input until full
+    fed: usize,
+}
+
+/// String-machine style ensemble: each channel of a stereo signal goes through several choruses
+/// with evenly spread phases and slightly detuned rates. The right channel voices are offset by
+/// half the phase spacing from the left ones, which widens the image while keeping it in place.
+///
+/// The voices are panned across the stereo field, from the slowest on the left to the fastest on
+/// the right, each channel keeping only its own side of every voice.
+pub struct Ensemble {
+    sample_rate: f32,
+    voices: Vec<Voice>,
+    count: usize,
+    rate: f32,
+}
+
+impl Ensemble {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut ensemble = Self {
+            sample_rate,
+            voices: (0..MAX_VOICES)
+                .map(|_| Voice {
+                    choruses: [
+                        Chorus::new(sample_rate, MAX_DEPTH),
+                        Chorus::new(sample_rate, MAX_DEPTH),
+                    ],
+                    pan: [0.0; 2],
+                    gain: 0.0,
+                    fed: 0,
+                })
+                .collect(),
+            count: 0,
+            rate: 0.6,
+        };
+        ensemble.set_voices(4);
+        for voice in &mut ensemble.voices[..ensemble.count] {
+            voice.gain = 1.0;
+        }
+        ensemble
+    }
+
+    /// Sets the number of voices, between `MIN_VOICES` and `MAX_VOICES`. Voices that are still
+    /// heard keep their phase, and voices are faded in and out so that the change doesn't click.
+    pub fn set_voices(&mut self, count: usize) {
+        let count = count.max(MIN_VOICES).min(MAX_VOICES);
+        if count == self.count {
+            return;
+        }
+        self.count = count;
+        // Silent voices can be moved freely, to their place in the new spread
+        for (v, voice) in self.voices.iter_mut().enumerate().take(count) {
+            if voice.gain == 0.0 {
+                for (c, chorus) in voice.choruses.iter_mut().enumerate() {
+                    chorus.set_pos((v as f32 + 0.5 * c as f32) / count as f32);
+                }
+                voice.pan = pan(spread(v, count));
+            }
+        }
+        self.retune();
+    }
+
+    /// Sets the base rate of the voices, in Hz.
+    pub fn set_rate(&mut self, rate: f32) {
+        if rate != self.rate {
+            self.rate = rate;
+            self.retune();
+        }
+    }
+
+    fn retune(&mut self) {
+        let (rate, count) = (self.rate, self.count);
+        for (v, voice) in self.voices.iter_mut().enumerate() {
+            // Voices fading out keep the rate they had
+            if v < count {
+                let frequency = rate * (1.0 + DETUNE * spread(v, count));
+                for chorus in &mut voice.choruses {
+                    chorus.set_frequency(Hz::from_frequency(frequency));
+                }
+            }
+        }
+    }
+
+    /// Sets the swing of the voices, in seconds.
+    pub fn set_depth(&mut self, depth: f32) {
+        for voice in &mut self.voices {
+            voice.choruses.iter_mut().for_each(|c| c.set_depth(depth));
+        }
+    }
+}
+
+/// Position of voice `v` out of `count`, from -1 to 1.
+fn spread(v: usize, count: usize) -> f32 {
+    2.0 * v as f32 / (count - 1) as f32 - 1.0
+}
+
+/// Constant-power gains of the left and right channels for a position from -1 (left) to 1
+/// (right).
+fn pan(position: f32) -> [f32; 2] {
+    let angle = FRAC_PI_4 * (1.0 + position);
+    [angle.cos(), angle.sin()]
+}
+
+impl Process for Ensemble {
+    type T = f32;
+    const NIN: usize = 2;
+    const NOUT: usize = 2;
+
+    #[inline(always)]
+    fn process(&mut self, ctx: &AudioContext, input_frame: &[f32], output_frame: &mut [f32]) {
+        let step = 1.0 / (FADE * self.sample_rate);
+        let line = (2.0 * MAX_DEPTH * self.sample_rate).ceil() as usize;
+        let (mut out, mut total) = ([0.0; 2], [0.0; 2]);
+        for (v, voice) in self.voices.iter_mut().enumerate() {
+            let used = v < self.count;
+            if !used && voice.gain == 0.0 {
+                voice.fed = 0;
+                continue;
+            }
+            voice.fed += 1;
+            voice.gain = if !used {
+                (voice.gain - step).max(0.0)
+            } else if voice.fed > line {
+                (voice.gain + step).min(1.0)
+            } else {
+                voice.gain
+            };
+            for c in 0..2 {
+                let gain = voice.gain * voice.pan[c];
+                out[c] += gain * voice.choruses[c].process_single_channel(ctx, input_frame[c]);
+                total[c] += gain;
+            }
+        }
+
+        // Normalized by the summed gains, so that the voices keep the input level when they line
+        // up, as they do at zero depth, and never go above it otherwise
+        for c in 0..2 {
+            output_frame[c] = if total[c] > 0.0 {
+                out[c] / total[c]
+            } else {
+                0.0
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ensemble_keeps_channels_apart() {
+        let ctx = AudioContext::new(48000.0);
+        for count in MIN_VOICES..=MAX_VOICES {
+            let mut ensemble = Ensemble::new(48000.0);
+            ensemble.set_voices(count);
+            ensemble.set_depth(0.0);
+            let mut output = [0.0; 2];
+            for _ in 0..4800 {
+                ensemble.process(&ctx, &[1.0, 0.0], &mut output);
+            }
+            assert!((output[0] - 1.0).abs() < 1e-5);
+            assert!(output[1].abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_voices_are_panned_at_constant_power() {
+        for count in MIN_VOICES..=MAX_VOICES {
+            let gains: Vec<_> = (0..count).map(|v| pan(spread(v, count))).collect();
+            for g in &gains {
+                assert!((g[0] * g[0] + g[1] * g[1] - 1.0).abs() < 1e-6);
+            }
+            assert!((gains[0][0] - 1.0).abs() < 1e-6);
+            assert!((gains[count - 1][1] - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_voice_changes_are_smooth() {
+        let ctx = AudioContext::new(48000.0);
+        let mut ensemble = Ensemble::new(48000.0);
+        ensemble.set_depth(0.002);
+        let mut output = [0.0; 2];
+        let mut previous = [0.0; 2];
+        for i in 0..48000 {
+            if i % 6000 == 0 {
+                ensemble.set_voices(MIN_VOICES + (i / 6000) % (MAX_VOICES - MIN_VOICES + 1));
+            }
+            let x = (i as f32 * 0.01).sin();
+            ensemble.process(&ctx, &[x, x], &mut output);
+            if i > 1000 {
+                for (y, p) in output.iter().zip(previous.iter()) {
+                    assert!((y - p).abs() < 0.05, "jump at sample {}", i);
+                }
+            }
+            previous = output;
+        }
+    }
+}
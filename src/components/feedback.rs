@@ -58,4 +58,10 @@ where
         self.backward
             .process(ctx, output_frame, &mut self.fb_buffer);
     }
+
+    fn reset(&mut self) {
+        self.forward.reset();
+        self.backward.reset();
+        self.fb_buffer = [P::T::ZERO; N];
+    }
 }
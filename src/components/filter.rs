@@ -0,0 +1,157 @@
+use audio::Sample;
+use num_traits::{Float, FloatConst};
+
+use super::{AudioContext, SingleChannelProcess};
+
+/// One-pole lowpass filter, mostly used for damping inside feedback paths.
+pub struct OnePole<T> {
+    coeff: T,
+    state: T,
+}
+
+impl<T: Float + FloatConst> OnePole<T> {
+    pub fn new(samplerate: T, cutoff: T) -> Self {
+        let mut filter = Self {
+            coeff: T::one(),
+            state: T::zero(),
+        };
+        filter.set_cutoff(samplerate, cutoff);
+        filter
+    }
+
+    /// Filter letting everything through, to be configured later with `set_cutoff`.
+    pub fn bypass() -> Self {
+        Self {
+            coeff: T::one(),
+            state: T::zero(),
+        }
+    }
+
+    pub fn set_cutoff(&mut self, samplerate: T, cutoff: T) {
+        let nyquist = samplerate / (T::one() + T::one());
+        self.coeff = if cutoff >= nyquist {
+            T::one()
+        } else {
+            T::one() - (-T::TAU() * cutoff / samplerate).exp()
+        };
+    }
+}
+
+impl<T: Sample + Float> SingleChannelProcess for OnePole<T> {
+    type T = T;
+
+    #[inline(always)]
+    fn process_single_channel(&mut self, _: &AudioContext, value: Self::T) -> Self::T {
+        self.state = self.state + self.coeff * (value - self.state);
+        self.state
+    }
+
+    fn reset_single_channel(&mut self) {
+        self.state = T::zero();
+    }
+}
+
+/// Feedback gain with high frequency damping, for the backward path of feedback loops.
+pub struct DampedGain<T> {
+    pub gain: T,
+    pub damping: OnePole<T>,
+}
+
+impl<T: Float + FloatConst> DampedGain<T> {
+    pub fn new(gain: T) -> Self {
+        Self {
+            gain,
+            damping: OnePole::bypass(),
+        }
+    }
+}
+
+impl<T: Sample + Float> SingleChannelProcess for DampedGain<T> {
+    type T = T;
+
+    #[inline(always)]
+    fn process_single_channel(&mut self, ctx: &AudioContext, value: Self::T) -> Self::T {
+        self.damping.process_single_channel(ctx, value) * self.gain
+    }
+
+    fn reset_single_channel(&mut self) {
+        self.damping.reset_single_channel();
+    }
+}
+
+/// Biquad filter in transposed direct form II, with coefficients from the RBJ cookbook.
+pub struct Biquad<T> {
+    b0: T,
+    b1: T,
+    b2: T,
+    a1: T,
+    a2: T,
+    s1: T,
+    s2: T,
+}
+
+impl<T: Float + FloatConst> Biquad<T> {
+    pub fn lowpass(samplerate: T, freq: T, q: T) -> Self {
+        let mut filter = Self::identity();
+        filter.set_lowpass(samplerate, freq, q);
+        filter
+    }
+
+    pub fn highpass(samplerate: T, freq: T, q: T) -> Self {
+        let mut filter = Self::identity();
+        filter.set_highpass(samplerate, freq, q);
+        filter
+    }
+
+    fn identity() -> Self {
+        Self {
+            b0: T::one(),
+            b1: T::zero(),
+            b2: T::zero(),
+            a1: T::zero(),
+            a2: T::zero(),
+            s1: T::zero(),
+            s2: T::zero(),
+        }
+    }
+
+    pub fn set_lowpass(&mut self, samplerate: T, freq: T, q: T) {
+        let (cos, alpha) = Self::prewarp(samplerate, freq, q);
+        let two = T::one() + T::one();
+        let b1 = T::one() - cos;
+        self.set_coefficients(b1 / two, b1, b1 / two, T::one() + alpha, -two * cos, T::one() - alpha);
+    }
+
+    pub fn set_highpass(&mut self, samplerate: T, freq: T, q: T) {
+        let (cos, alpha) = Self::prewarp(samplerate, freq, q);
+        let two = T::one() + T::one();
+        let b1 = T::one() + cos;
+        self.set_coefficients(b1 / two, -b1, b1 / two, T::one() + alpha, -two * cos, T::one() - alpha);
+    }
+
+    fn prewarp(samplerate: T, freq: T, q: T) -> (T, T) {
+        let w0 = T::TAU() * freq / samplerate;
+        let alpha = w0.sin() / (q + q);
+        (w0.cos(), alpha)
+    }
+
+    fn set_coefficients(&mut self, b0: T, b1: T, b2: T, a0: T, a1: T, a2: T) {
+        self.b0 = b0 / a0;
+        self.b1 = b1 / a0;
+        self.b2 = b2 / a0;
+        self.a1 = a1 / a0;
+        self.a2 = a2 / a0;
+    }
+}
+
+impl<T: Sample + Float> SingleChannelProcess for Biquad<T> {
+    type T = T;
+
+    #[inline(always)]
+    fn process_single_channel(&mut self, _: &AudioContext, value: Self::T) -> Self::T {
+        let y = self.b0 * value + self.s1;
+        self.s1 = self.b1 * value - self.a1 * y + self.s2;
+        self.s2 = self.b2 * value - self.a2 * y;
+        y
+    }
+}
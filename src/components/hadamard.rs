@@ -55,7 +55,7 @@ impl<T: audio::Sample + Scalar + Float + ClosedAdd + ClosedMul + ClosedDiv, cons
 }
 
 // FIXME: Wait for const int operations to replace this with a proper generic function
-fn hadamard<T: Scalar + One + Neg<Output = T>>(n: usize) -> DMatrix<T> {
+pub(crate) fn hadamard<T: Scalar + One + Neg<Output = T>>(n: usize) -> DMatrix<T> {
     if n == 1 {
         // DMatrix::from_row_slice(2, 2, &[T::one(), T::one(), T::one(), T::one().neg()])
         DMatrix::from_fn(1, 1, |_, _| T::one())
@@ -0,0 +1,133 @@
+use std::f32::consts::{PI, TAU};
+
+use super::{rng::Rng, AudioContext};
+
+/// Waveform of an `Lfo`. All shapes are bipolar; sine, triangle and saw start at zero, and square
+/// starts high.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LfoShape {
+    Sine,
+    Triangle,
+    /// Rising saw
+    Saw,
+    Square,
+    /// New random value held for each cycle
+    SampleAndHold,
+    /// New random value every cycle, smoothly interpolated from the previous one
+    SmoothRandom,
+}
+
+impl LfoShape {
+    pub fn from_param(value: f32) -> Self {
+        match value.round() as i32 {
+            1 => Self::Triangle,
+            2 => Self::Saw,
+            3 => Self::Square,
+            4 => Self::SampleAndHold,
+            5 => Self::SmoothRandom,
+            _ => Self::Sine,
+        }
+    }
+}
+
+/// Rate of an `Lfo`, either free running or synced to the host tempo.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LfoRate {
+    /// Frequency, in Hz
+    Hz(f32),
+    /// Length of a cycle, in beats
+    Beats(f32),
+}
+
+/// Low frequency oscillator for modulating parameters. The phase is advanced by exactly the
+/// frequency over the sample rate every sample, so it doesn't drift over time.
+pub struct Lfo {
+    pub shape: LfoShape,
+    pub rate: LfoRate,
+    /// Phase in cycles, kept in double precision so that slow rates stay accurate
+    phase: f64,
+    rng: Rng,
+    /// Held value of the current cycle, and the one of the next cycle
+    random: [f32; 2],
+}
+
+impl Lfo {
+    /// Creates an LFO starting at `phase` (between 0 and 1) of its cycle, `seed` setting the
+    /// sequence of the random shapes.
+    pub fn new(shape: LfoShape, rate: LfoRate, phase: f32, seed: u32) -> Self {
+        let mut rng = Rng::new(seed);
+        let random = [rng.next_bipolar(), rng.next_bipolar()];
+        Self {
+            shape,
+            rate,
+            phase: phase.rem_euclid(1.0) as f64,
+            rng,
+            random,
+        }
+    }
+
+    /// Moves the LFO to `phase` (between 0 and 1) of its cycle.
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = phase.rem_euclid(1.0) as f64;
+    }
+
+    /// Frequency of the LFO in Hz, following the tempo of `ctx` when synced.
+    pub fn frequency(&self, ctx: &AudioContext) -> f32 {
+        match self.rate {
+            LfoRate::Hz(frequency) => frequency,
+            LfoRate::Beats(beats) => ctx.bpm / (60.0 * beats.max(1e-3)),
+        }
+    }
+
+    /// Value of the LFO at the current sample, between -1 and 1.
+    pub fn value(&self) -> f32 {
+        let p = self.phase as f32;
+        match self.shape {
+            LfoShape::Sine => (TAU * p).sin(),
+            LfoShape::Triangle => 1.0 - 4.0 * ((p + 0.25).fract() - 0.5).abs(),
+            LfoShape::Saw => 2.0 * (p + 0.5).fract() - 1.0,
+            LfoShape::Square => {
+                if p < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LfoShape::SampleAndHold => self.random[0],
+            LfoShape::SmoothRandom => {
+                let t = 0.5 - 0.5 * (PI * p).cos();
+                self.random[0] + t * (self.random[1] - self.random[0])
+            }
+        }
+    }
+
+    /// Returns the value at the current sample, and advances to the next one.
+    pub fn next(&mut self, ctx: &AudioContext) -> f32 {
+        let value = self.value();
+        self.phase += self.frequency(ctx) as f64 / ctx.sample_rate as f64;
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            self.random = [self.random[1], self.rng.next_bipolar()];
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lfo_phase_does_not_drift() {
+        let ctx = AudioContext::new(44100.0);
+        // Two beats at 120 BPM is one cycle per second
+        let mut synced = Lfo::new(LfoShape::Saw, LfoRate::Beats(2.0), 0.0, 0);
+        let mut free = Lfo::new(LfoShape::Saw, LfoRate::Hz(1.0), 0.0, 0);
+        for _ in 0..100 * 44100 {
+            synced.next(&ctx);
+            free.next(&ctx);
+        }
+        assert!(synced.value().abs() < 1e-3);
+        assert!(free.value().abs() < 1e-3);
+    }
+}
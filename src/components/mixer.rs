@@ -0,0 +1,149 @@
+use std::ops::Neg;
+
+use nalgebra::{ClosedAdd, ClosedDiv, ClosedMul, SMatrix, SVectorSlice, SVectorSliceMut, Scalar};
+use num_traits::Float;
+
+use super::{hadamard::hadamard, AudioContext, Process};
+
+/// Gain-normalized matrix mixer between arbitrary channel counts.
+///
+/// The transfer matrix is a blend between a polarity-alternating routing (input `k % IN` going to
+/// output `k % OUT` for each `k` up to the larger channel count, every other copy inverted) and a
+/// sub-matrix of a Hadamard matrix, controlled by the decorrelation amount. The blended matrix is then normalized so that upmixing preserves the
+/// energy of each input, and downmixing preserves the level of uncorrelated inputs.
+pub struct Mixer<T: Scalar, const IN: usize, const OUT: usize> {
+    decorrelation: T,
+    /// Whether the matrix is normalized like the one of `Mixer<T, OUT, IN>`
+    transposed: bool,
+    direct: SMatrix<T, OUT, IN>,
+    orthogonal: SMatrix<T, OUT, IN>,
+    transfer: SMatrix<T, OUT, IN>,
+}
+
+impl<T, const IN: usize, const OUT: usize> Mixer<T, IN, OUT>
+where
+    T: Scalar + Float + ClosedAdd + ClosedMul + ClosedDiv + Neg<Output = T>,
+{
+    pub fn new(decorrelation: T) -> Self {
+        Self::with_orientation(decorrelation, false)
+    }
+
+    /// Creates the mixer whose matrix is the transpose of the one of a `Mixer<T, OUT, IN>` at the
+    /// same decorrelation. Both orientations only differ in normalization when `IN == OUT`.
+    pub fn transposed(decorrelation: T) -> Self {
+        Self::with_orientation(decorrelation, true)
+    }
+
+    fn with_orientation(decorrelation: T, transposed: bool) -> Self {
+        let size = IN.max(OUT).next_power_of_two();
+        let h = hadamard::<T>(size);
+        let direct = SMatrix::from_fn(|o, i| {
+            (0..IN.max(OUT))
+                .filter(|k| k % OUT == o && k % IN == i)
+                .map(|k| {
+                    if (k / IN.min(OUT)) % 2 == 0 {
+                        T::one()
+                    } else {
+                        T::one().neg()
+                    }
+                })
+                .fold(T::zero(), |acc, x| acc + x)
+        });
+        let orthogonal = SMatrix::from_fn(|o, i| h[(o * size / OUT, i * size / IN)]);
+        let mut mixer = Self {
+            decorrelation,
+            transposed,
+            direct,
+            orthogonal,
+            transfer: SMatrix::zeros(),
+        };
+        mixer.update_transfer();
+        mixer
+    }
+
+    pub fn set_decorrelation(&mut self, decorrelation: T) {
+        if decorrelation != self.decorrelation {
+            self.decorrelation = decorrelation;
+            self.update_transfer();
+        }
+    }
+
+    fn update_transfer(&mut self) {
+        let d = self.decorrelation;
+        self.transfer = self.direct * (T::one() - d) + self.orthogonal * d;
+        if OUT > IN || (OUT == IN && !self.transposed) {
+            for mut col in self.transfer.column_iter_mut() {
+                let norm = Float::sqrt(col.dot(&col));
+                if norm > T::zero() {
+                    col /= norm;
+                }
+            }
+        } else {
+            for mut row in self.transfer.row_iter_mut() {
+                let norm = Float::sqrt(row.dot(&row));
+                if norm > T::zero() {
+                    row /= norm;
+                }
+            }
+        }
+    }
+}
+
+impl<T, const IN: usize, const OUT: usize> Process for Mixer<T, IN, OUT>
+where
+    T: audio::Sample + Scalar + Float + ClosedAdd + ClosedMul + ClosedDiv,
+{
+    type T = T;
+    const NIN: usize = IN;
+    const NOUT: usize = OUT;
+
+    #[inline(always)]
+    fn process(
+        &mut self,
+        _: &AudioContext,
+        inputs: &[<Self as Process>::T],
+        outputs: &mut [<Self as Process>::T],
+    ) {
+        let invec = SVectorSlice::<T, IN>::from_slice(inputs);
+        let mut outvec = SVectorSliceMut::<T, OUT>::from_slice(outputs);
+        self.transfer.mul_to(&invec, &mut outvec);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upmix_preserves_energy() {
+        for &d in &[0.0, 0.3, 1.0] {
+            let mixer = Mixer::<f32, 2, 8>::new(d);
+            for col in mixer.transfer.column_iter() {
+                assert!((col.dot(&col).sqrt() - 1.0).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_downmix_normalized() {
+        for &d in &[0.0, 0.3, 1.0] {
+            let mixer = Mixer::<f32, 8, 2>::new(d);
+            for row in mixer.transfer.row_iter() {
+                assert!((row.dot(&row).sqrt() - 1.0).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_downmix_alternates_polarity() {
+        let mixer = Mixer::<f32, 8, 2>::new(0.0);
+        assert_eq!(mixer.transfer.row(0).iter().filter(|&&x| x < 0.0).count(), 2);
+        assert_eq!(mixer.transfer.row(1).iter().filter(|&&x| x < 0.0).count(), 2);
+    }
+
+    #[test]
+    fn test_stereo_identity() {
+        let mixer = Mixer::<f32, 2, 2>::new(0.0);
+        assert_eq!(mixer.transfer, SMatrix::<f32, 2, 2>::identity());
+    }
+}
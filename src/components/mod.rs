@@ -4,65 +4,59 @@ use audio::Sample;
 
 
 pub(crate) mod allpass;
+// Only fully used by the output layouts of their own features
+#[cfg_attr(not(any(feature = "layout-foa", feature = "layout-toa")), allow(dead_code))]
+pub(crate) mod ambisonics;
+#[cfg_attr(not(feature = "layout-binaural"), allow(dead_code))]
+pub(crate) mod binaural;
 pub mod chorus;
+pub(crate) mod comb;
+pub(crate) mod crossover;
 pub mod drywet;
 pub(crate) mod delay;
+pub(crate) mod ensemble;
 pub(crate) mod feedback;
+pub(crate) mod filter;
 pub(crate) mod hadamard;
+pub(crate) mod lfo;
+pub(crate) mod mixer;
+pub(crate) mod modulated_delay;
+pub(crate) mod multitap;
+pub(crate) mod nested;
 pub(crate) mod parallel;
+pub(crate) mod rng;
+pub(crate) mod scattering;
+pub(crate) mod rotation;
+pub(crate) mod schroeder;
 pub(crate) mod seq;
-pub(crate) mod spread;
-pub(crate) mod stereoize;
+pub(crate) mod velvet;
+pub(crate) mod width;
 
-mod ext {
-    use std::time::Duration;
-
-    use num_traits::{Float, FromPrimitive};
-
-    pub trait DurationFloatExt: Sized {
-        fn from_seconds<T: Float>(&self, secs: T) -> Self;
-        fn as_seconds<T: Float + FromPrimitive>(&self) -> T;
-    }
-
-    impl DurationFloatExt for Duration {
-        fn from_seconds<T: Float>(&self, secs: T) -> Self {
-            Duration::from_nanos((secs * T::one().powi(9)).to_u64().unwrap())
-        }
-
-        fn as_seconds<T: Float + FromPrimitive>(&self) -> T {
-            T::from_u64(self.as_secs()).unwrap()
-                + T::from_u32(self.subsec_nanos()).unwrap() / T::from_u64(1_000_000_000).unwrap()
-        }
-    }
-
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-        use std::time::Duration;
-
-        #[test]
-        fn test_as_seconds() {
-            let dur = Duration::from_secs_f64(1.1);
-            assert_eq!(1.1, dur.as_seconds::<f32>());
-        }
-    }
-}
+/// Speed of sound in air, in m/s
+pub(crate) const SPEED_OF_SOUND: f32 = 343.0;
 
 pub(crate) struct AudioContext {
     pub(crate) sample_rate: f32,
     pub(crate) sample_count: u128,
+    /// Tempo of the host, in beats per minute
+    pub(crate) bpm: f32,
 }
 
 impl AudioContext {
+    /// Context at the start of playback, for tests.
+    #[cfg(test)]
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            sample_count: 0,
+            bpm: 120.0,
+        }
+    }
+
     pub fn get_runtime(&self) -> Duration {
         let nanos = self.sample_count as u64 / (self.sample_rate * 1e9) as u64;
         Duration::from_nanos(nanos)
     }
-
-    pub fn tick_length(&self) -> Duration {
-        let tick = (1e9 / self.sample_rate as f64).floor() as u64;
-        Duration::from_nanos(tick)
-    }
 }
 
 pub(crate) trait Process {
@@ -76,12 +70,19 @@ pub(crate) trait Process {
         input_frame: &[Self::T],
         output_frame: &mut [Self::T],
     );
+
+    /// Clears the signal held inside, as if nothing had been processed yet. Processes without
+    /// memory keep the default, which does nothing.
+    fn reset(&mut self) {}
 }
 
 pub(crate) trait SingleChannelProcess {
     type T: Sample;
 
     fn process_single_channel(&mut self, ctx: &AudioContext, value: Self::T) -> Self::T;
+
+    /// See `Process::reset`.
+    fn reset_single_channel(&mut self) {}
 }
 
 impl<P: SingleChannelProcess> Process for P {
@@ -97,4 +98,8 @@ impl<P: SingleChannelProcess> Process for P {
     ) {
         output_frame[0] = self.process_single_channel(ctx, input_frame[0])
     }
+
+    fn reset(&mut self) {
+        self.reset_single_channel()
+    }
 }
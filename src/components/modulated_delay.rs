@@ -0,0 +1,156 @@
+use super::{
+    delay::DelayLine,
+    filter::OnePole,
+    lfo::{Lfo, LfoRate, LfoShape},
+    rng::Rng,
+    AudioContext, SingleChannelProcess,
+};
+
+/// Waveform of a `ModulatedDelay`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ModShape {
+    /// Periodic sine
+    Sine,
+    /// Random walk bouncing between the extremes, lowpassed at the rate
+    Wander,
+    /// Random values at the rate, smoothly interpolated
+    Noise,
+}
+
+impl ModShape {
+    pub fn from_param(value: f32) -> Self {
+        match value.round() as i32 {
+            1 => Self::Wander,
+            2 => Self::Noise,
+            _ => Self::Sine,
+        }
+    }
+}
+
+/// Short delay whose length is modulated around `depth`, meant to sit inside feedback loops where
+/// the slowly moving delays keep the modes of the loop from ringing.
+///
+/// The phase and seed are per instance so that parallel lines move independently, spreading the
+/// modulation over the stereo field.
+pub struct ModulatedDelay {
+    line: DelayLine<f32>,
+    sample_rate: f32,
+    pub shape: ModShape,
+    /// Modulation rate, in Hz
+    rate: f32,
+    /// Modulation depth, in seconds; the delay moves between 0 and twice the depth
+    pub depth: f32,
+    /// Modulation of the sine and noise shapes
+    lfo: Lfo,
+    rng: Rng,
+    /// Position of the random walk
+    walk: f32,
+    smoothing: OnePole<f32>,
+}
+
+impl ModulatedDelay {
+    /// Creates a delay modulated by up to `max_depth` seconds, starting at `phase` (between 0 and
+    /// 1) of its cycle.
+    pub fn new(sample_rate: f32, max_depth: f32, phase: f32, seed: u32) -> Self {
+        Self {
+            line: DelayLine::new((2.0 * max_depth * sample_rate).ceil() as usize + 2),
+            sample_rate,
+            shape: ModShape::Sine,
+            rate: 0.5,
+            depth: 0.0,
+            lfo: Lfo::new(LfoShape::Sine, LfoRate::Hz(0.5), phase, seed),
+            rng: Rng::new(seed),
+            walk: 0.0,
+            smoothing: OnePole::new(sample_rate, 0.5),
+        }
+    }
+
+    /// Sets the modulation rate, in Hz.
+    pub fn set_rate(&mut self, rate: f32) {
+        if rate != self.rate {
+            self.rate = rate;
+            self.lfo.rate = LfoRate::Hz(rate);
+            self.smoothing.set_cutoff(self.sample_rate, rate);
+        }
+    }
+
+    /// Next value of the modulation, between -1 and 1.
+    fn modulation(&mut self, ctx: &AudioContext) -> f32 {
+        self.lfo.shape = match self.shape {
+            ModShape::Noise => LfoShape::SmoothRandom,
+            _ => LfoShape::Sine,
+        };
+        let value = self.lfo.next(ctx);
+        if self.shape != ModShape::Wander {
+            return value;
+        }
+        // Steps are scaled so that the walk crosses its range about once per period
+        let step = self.rate / self.sample_rate;
+        self.walk += 2.0 * step.sqrt() * self.rng.next_bipolar();
+        if self.walk.abs() > 1.0 {
+            self.walk = self.walk.signum() * 2.0 - self.walk;
+        }
+        self.smoothing.process_single_channel(ctx, self.walk)
+    }
+}
+
+impl SingleChannelProcess for ModulatedDelay {
+    type T = f32;
+
+    #[inline(always)]
+    fn process_single_channel(&mut self, ctx: &AudioContext, value: f32) -> f32 {
+        let modulation = self.modulation(ctx);
+        self.line.push_pop(value);
+        let depth = self.depth * self.sample_rate;
+        self.line.read(depth * (1.0 + modulation))
+    }
+
+    fn reset_single_channel(&mut self) {
+        self.line.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_depth_passes_through() {
+        let ctx = AudioContext::new(48000.0);
+        for shape in [ModShape::Sine, ModShape::Wander, ModShape::Noise] {
+            let mut delay = ModulatedDelay::new(48000.0, 0.005, 0.25, 3);
+            delay.shape = shape;
+            delay.set_rate(2.0);
+            for i in 0..1000 {
+                let x = (i as f32 * 0.1).sin();
+                assert_eq!(delay.process_single_channel(&ctx, x), x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_delay_moves_within_depth() {
+        let ctx = AudioContext::new(48000.0);
+        let depth = 0.005;
+        let samples = depth * 48000.0;
+        for shape in [ModShape::Sine, ModShape::Wander, ModShape::Noise] {
+            let mut delay = ModulatedDelay::new(48000.0, depth, 0.25, 3);
+            delay.shape = shape;
+            delay.depth = depth;
+            delay.set_rate(2.0);
+            // A ramp delayed by d samples comes out d below the input
+            let (mut min, mut max) = (f32::INFINITY, f32::NEG_INFINITY);
+            for i in 0..48000 {
+                let x = i as f32;
+                let y = delay.process_single_channel(&ctx, x);
+                if i > 1000 {
+                    let d = x - y;
+                    min = min.min(d);
+                    max = max.max(d);
+                }
+            }
+            assert!(min >= -1e-2 && max <= 2.0 * samples + 1e-2, "{:?}", shape);
+            assert!(max - min > 0.25 * samples, "{:?}", shape);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use super::{delay::DelayLine, filter::OnePole, AudioContext, Process, SingleChannelProcess};
+
+/// A single tap of a `MultiTap` delay.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tap {
+    /// Delay in seconds, before time scaling
+    pub delay: f32,
+    pub gain: f32,
+    pub input: usize,
+    pub output: usize,
+    /// Cutoff of the one-pole lowpass filtering the tap, if any
+    pub cutoff: Option<f32>,
+}
+
+struct TapState {
+    delay: f32,
+    filter: Option<OnePole<f32>>,
+}
+
+/// Delay reading many taps out of I input lines, each with its own gain and filtering, and summed
+/// into O outputs. Taps are loaded from a table, and their delay times can be scaled together.
+///
+/// Room for the largest table is allocated up front, so that loading taps never allocates.
+pub struct MultiTap<const I: usize, const O: usize> {
+    sample_rate: f32,
+    time_scale: f32,
+    max_taps: usize,
+    taps: Vec<Tap>,
+    state: Vec<TapState>,
+    lines: Vec<DelayLine<f32>>,
+}
+
+impl<const I: usize, const O: usize> MultiTap<I, O> {
+    pub fn new(sample_rate: f32, max_delay: f32, max_taps: usize) -> Self {
+        Self {
+            sample_rate,
+            time_scale: 1.0,
+            max_taps,
+            taps: Vec::with_capacity(max_taps),
+            state: Vec::with_capacity(max_taps),
+            lines: (0..I)
+                .map(|_| DelayLine::new((sample_rate * max_delay) as usize + 2))
+                .collect(),
+        }
+    }
+
+    /// Replaces the taps with the given table. Taps with out of range inputs or outputs are
+    /// ignored, and so are taps past the first `max_taps`.
+    pub fn load(&mut self, taps: impl IntoIterator<Item = Tap>) {
+        self.taps.clear();
+        self.taps.extend(
+            taps.into_iter()
+                .filter(|t| t.input < I && t.output < O)
+                .take(self.max_taps),
+        );
+        let sample_rate = self.sample_rate;
+        let time_scale = self.time_scale;
+        self.state.clear();
+        self.state.extend(self.taps.iter().map(|t| TapState {
+            delay: t.delay * time_scale * sample_rate,
+            filter: t.cutoff.map(|c| OnePole::new(sample_rate, c)),
+        }));
+    }
+
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        if time_scale == self.time_scale {
+            return;
+        }
+        self.time_scale = time_scale;
+        for (tap, state) in self.taps.iter().zip(self.state.iter_mut()) {
+            state.delay = tap.delay * time_scale * self.sample_rate;
+        }
+    }
+}
+
+impl<const I: usize, const O: usize> Process for MultiTap<I, O> {
+    type T = f32;
+    const NIN: usize = I;
+    const NOUT: usize = O;
+
+    fn process(&mut self, ctx: &AudioContext, input_frame: &[f32], output_frame: &mut [f32]) {
+        for (line, &x) in self.lines.iter_mut().zip(input_frame.iter()) {
+            line.push_pop(x);
+        }
+
+        output_frame.iter_mut().for_each(|x| *x = 0.0);
+        for (tap, state) in self.taps.iter().zip(self.state.iter_mut()) {
+            let x = self.lines[tap.input].read(state.delay);
+            let x = match &mut state.filter {
+                Some(filter) => filter.process_single_channel(ctx, x),
+                None => x,
+            };
+            output_frame[tap.output] += tap.gain * x;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tap_routing() {
+        let ctx = AudioContext::new(1000.0);
+        let mut multitap = MultiTap::<2, 3>::new(1000.0, 0.1, 1);
+        multitap.load(vec![Tap {
+            delay: 0.01,
+            gain: 0.5,
+            input: 1,
+            output: 2,
+            cutoff: None,
+        }]);
+        let mut out = [0.0; 3];
+        for i in 0..20 {
+            let x = if i == 0 { 1.0 } else { 0.0 };
+            multitap.process(&ctx, &[0.0, x], &mut out);
+            let expected = if i == 10 { 0.5 } else { 0.0 };
+            assert!((out[2] - expected).abs() < 1e-6, "sample {}: {:?}", i, out);
+            assert_eq!(out[0], 0.0);
+        }
+    }
+
+    #[test]
+    fn test_load_stays_within_allocation() {
+        let mut multitap = MultiTap::<1, 1>::new(1000.0, 0.1, 4);
+        let (taps, state) = (multitap.taps.as_ptr(), multitap.state.as_ptr());
+        multitap.load((0..10).map(|i| Tap {
+            delay: i as f32 / 1000.0,
+            gain: 1.0,
+            input: 0,
+            output: 0,
+            cutoff: Some(500.0),
+        }));
+        assert_eq!(multitap.taps.len(), 4);
+        assert_eq!(multitap.taps.as_ptr(), taps);
+        assert_eq!(multitap.state.as_ptr(), state);
+    }
+}
@@ -0,0 +1,77 @@
+use audio::Sample;
+use num_traits::{Float, FromPrimitive};
+
+use super::{delay::DelayLine, AudioContext, Process, SingleChannelProcess};
+
+/// Nested allpass filter, after Gardner: a Schroeder allpass whose delay is followed by another
+/// single channel process, usually an allpass or a `Sequence` of allpasses. As long as the inner
+/// process is an allpass, so is the whole structure, but the echo density builds up much faster.
+pub struct NestedAllpass<T, P> {
+    pub gain: T,
+    pub inner: P,
+    delay: T,
+    line: DelayLine<T>,
+}
+
+impl<T: Sample + Float + FromPrimitive, P: Process<T = T>> NestedAllpass<T, P> {
+    pub fn new(max_delay: usize, delay: T, gain: T, inner: P) -> Self {
+        debug_assert_eq!(P::NIN, 1);
+        debug_assert_eq!(P::NOUT, 1);
+        Self {
+            gain,
+            inner,
+            delay,
+            line: DelayLine::new(max_delay.max(2)),
+        }
+    }
+
+    /// Sets the delay of the outer allpass in samples, not counting the delay of the inner
+    /// process.
+    pub fn set_delay(&mut self, delay: T) {
+        self.delay = delay;
+    }
+}
+
+impl<T: Sample + Float + FromPrimitive, P: Process<T = T>> SingleChannelProcess
+    for NestedAllpass<T, P>
+{
+    type T = T;
+
+    #[inline(always)]
+    fn process_single_channel(&mut self, ctx: &AudioContext, value: T) -> T {
+        let delayed = self.line.read(self.delay.max(T::one()) - T::one());
+        let mut inner = [T::ZERO];
+        self.inner.process(ctx, &[delayed], &mut inner);
+        let w = value + self.gain * inner[0];
+        self.line.push_pop(w);
+        inner[0] - self.gain * w
+    }
+
+    fn reset_single_channel(&mut self) {
+        self.line.clear();
+        self.inner.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{schroeder::SchroederAllpass, seq::Sequence};
+
+    #[test]
+    fn test_double_nested_allpass_preserves_energy() {
+        let ctx = AudioContext::new(48000.0);
+        let inner = Sequence::new(
+            SchroederAllpass::new(32, 23.0, 0.4),
+            SchroederAllpass::new(16, 11.0, 0.6),
+        );
+        let mut allpass = NestedAllpass::new(64, 47.0, 0.3, inner);
+        let energy: f32 = (0..50000)
+            .map(|i| {
+                let x = if i == 0 { 1.0 } else { 0.0 };
+                allpass.process_single_channel(&ctx, x).powi(2)
+            })
+            .sum();
+        assert!((energy - 1.0).abs() < 1e-4);
+    }
+}
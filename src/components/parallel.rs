@@ -40,4 +40,8 @@ impl<P: SingleChannelProcess, const N: usize> Process for Parallel<P, N> {
             *out = self.data[i].process_single_channel(ctx, inp);
         }
     }
+
+    fn reset(&mut self) {
+        self.data.iter_mut().for_each(|p| p.reset_single_channel());
+    }
 }
@@ -0,0 +1,34 @@
+/// Xorshift pseudo-random generator, cheap enough to run at audio rate and deterministic for a
+/// given seed.
+#[derive(Copy, Clone, Debug)]
+pub struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        // A zero state would only ever produce zeros
+        Self {
+            state: seed.wrapping_mul(0x9E37_79B9).max(1),
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// Uniform value in [0, 1).
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// Uniform value in [-1, 1).
+    pub fn next_bipolar(&mut self) -> f32 {
+        2.0 * self.next_f32() - 1.0
+    }
+}
@@ -0,0 +1,98 @@
+use nalgebra::{SMatrix, SVector};
+
+use super::{delay::DelayLine, hadamard::hadamard, rng::Rng, AudioContext, Process};
+
+/// Delay-filled feedback matrix after Schlecht & Habets, "Scattering in Feedback Delay Networks":
+/// a cascade of orthogonal matrices interleaved with short per-channel delays. The whole cascade is
+/// paraunitary, so it is as lossless as a plain orthogonal matrix, but each pass through it
+/// scatters an impulse into many, multiplying the echo density of a feedback delay network.
+///
+/// Each matrix is a Hadamard matrix with random sign flips and a random permutation, so N must be
+/// a power of two.
+pub struct ScatteringMatrix<const N: usize> {
+    matrices: Vec<SMatrix<f32, N, N>>,
+    /// Delays between consecutive matrices, lines of zero delay being left out
+    delays: Vec<[Option<DelayLine<f32>>; N]>,
+}
+
+impl<const N: usize> ScatteringMatrix<N> {
+    /// Builds a cascade of `stages` delay stages, with delays up to `max_delay` samples.
+    pub fn new(stages: usize, max_delay: usize, seed: u32) -> Self {
+        let mut rng = Rng::new(seed);
+        let h = SMatrix::<f32, N, N>::from_column_slice(hadamard::<f32>(N).as_slice())
+            / (N as f32).sqrt();
+        let matrices = (0..=stages)
+            .map(|_| {
+                let mut permutation: Vec<usize> = (0..N).collect();
+                for i in (1..N).rev() {
+                    permutation.swap(i, rng.next_u32() as usize % (i + 1));
+                }
+                let signs: Vec<f32> = (0..N).map(|_| rng.next_bipolar().signum()).collect();
+                SMatrix::from_fn(|o, i| h[(o, permutation[i])] * signs[i])
+            })
+            .collect();
+        let delays = (0..stages)
+            .map(|_| {
+                [(); N].map(|_| {
+                    let delay = (rng.next_f32() * (max_delay + 1) as f32) as usize;
+                    if delay > 0 {
+                        Some(DelayLine::new(delay))
+                    } else {
+                        None
+                    }
+                })
+            })
+            .collect();
+        Self { matrices, delays }
+    }
+}
+
+impl<const N: usize> Process for ScatteringMatrix<N> {
+    type T = f32;
+    const NIN: usize = N;
+    const NOUT: usize = N;
+
+    fn process(&mut self, _: &AudioContext, input_frame: &[f32], output_frame: &mut [f32]) {
+        let mut v = self.matrices[0] * SVector::<f32, N>::from_column_slice(input_frame);
+        for (matrix, delays) in self.matrices[1..].iter().zip(self.delays.iter_mut()) {
+            for (x, delay) in v.iter_mut().zip(delays.iter_mut()) {
+                if let Some(line) = delay {
+                    *x = line.push_pop(*x);
+                }
+            }
+            v = matrix * v;
+        }
+        output_frame.copy_from_slice(v.as_slice());
+    }
+
+    fn reset(&mut self) {
+        for line in self.delays.iter_mut().flatten().flatten() {
+            line.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scattering_is_lossless() {
+        let ctx = AudioContext::new(48000.0);
+        let mut matrix = ScatteringMatrix::<8>::new(3, 50, 1);
+        let mut energy = 0.0;
+        let mut density = 0;
+        for n in 0..200 {
+            let mut input = [0.0; 8];
+            if n == 0 {
+                input[0] = 1.0;
+            }
+            let mut output = [0.0; 8];
+            matrix.process(&ctx, &input, &mut output);
+            energy += output.iter().map(|x| x * x).sum::<f32>();
+            density += output.iter().filter(|x| x.abs() > 1e-6).count();
+        }
+        assert!((energy - 1.0).abs() < 1e-4);
+        assert!(density > 8);
+    }
+}
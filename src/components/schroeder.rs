@@ -0,0 +1,70 @@
+use audio::Sample;
+use num_traits::{Float, FromPrimitive};
+
+use super::{delay::DelayLine, AudioContext, SingleChannelProcess};
+
+/// Schroeder allpass filter: a delay line wrapped in a feedback path of gain `gain` and a
+/// feedforward path of gain `-gain`, giving a flat magnitude response.
+///
+/// Unlike `Allpass`, which diffuses between channels, this is a single channel allpass filter.
+/// The delay can be fractional and changed at audio rate for modulated allpasses.
+pub struct SchroederAllpass<T> {
+    pub gain: T,
+    delay: T,
+    line: DelayLine<T>,
+}
+
+impl<T: Sample + Float + FromPrimitive> SchroederAllpass<T> {
+    pub fn new(max_delay: usize, delay: T, gain: T) -> Self {
+        Self {
+            gain,
+            delay,
+            line: DelayLine::new(max_delay.max(2)),
+        }
+    }
+
+    /// Sets the delay in samples, clamped between 1 and the length of the line.
+    pub fn set_delay(&mut self, delay: T) {
+        self.delay = delay;
+    }
+
+    /// Reads the internal delay line `delay` samples in the past, for output taps.
+    pub fn tap(&self, delay: T) -> T {
+        self.line.read(delay)
+    }
+}
+
+impl<T: Sample + Float + FromPrimitive> SingleChannelProcess for SchroederAllpass<T> {
+    type T = T;
+
+    #[inline(always)]
+    fn process_single_channel(&mut self, _: &AudioContext, value: T) -> T {
+        // The line hasn't been pushed to yet, so it's read one sample earlier
+        let delayed = self.line.read(self.delay.max(T::one()) - T::one());
+        let w = value + self.gain * delayed;
+        self.line.push_pop(w);
+        delayed - self.gain * w
+    }
+
+    fn reset_single_channel(&mut self) {
+        self.line.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allpass_preserves_energy() {
+        let ctx = AudioContext::new(48000.0);
+        let mut allpass = SchroederAllpass::new(64, 37.0, 0.6);
+        let energy: f32 = (0..20000)
+            .map(|i| {
+                let x = if i == 0 { 1.0 } else { 0.0 };
+                allpass.process_single_channel(&ctx, x).powi(2)
+            })
+            .sum();
+        assert!((energy - 1.0).abs() < 1e-4);
+    }
+}
@@ -34,6 +34,11 @@ impl<T: Sample, P: Process<T = T>, Q: Process<T = T>> Process for Sequence<T, P,
         self.pa.process(ctx, input_frame, &mut self.tmp_buf);
         self.pb.process(ctx, &self.tmp_buf, output_frame);
     }
+
+    fn reset(&mut self) {
+        self.pa.reset();
+        self.pb.reset();
+    }
 }
 
 #[macro_export]
@@ -0,0 +1,99 @@
+use super::{delay::DelayLine, rng::Rng, AudioContext, SingleChannelProcess};
+
+/// A single impulse of a velvet noise sequence.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Impulse {
+    /// Position in samples
+    pub position: usize,
+    /// Either 1 or -1
+    pub sign: f32,
+}
+
+/// Generates a velvet noise sequence of `length` samples: the time is split in periods of
+/// `sample_rate / density` samples, each holding a single impulse of random sign at a random
+/// position. The same seed always gives the same sequence.
+pub fn sequence(
+    density: f32,
+    length: usize,
+    sample_rate: f32,
+    seed: u32,
+) -> impl Iterator<Item = Impulse> {
+    let period = (sample_rate / density.max(1.0)).max(1.0);
+    let mut rng = Rng::new(seed);
+    (0..)
+        .map(move |m| {
+            let position = (m as f32 * period + rng.next_f32() * (period - 1.0)).round();
+            let sign = if rng.next_f32() < 0.5 { -1.0 } else { 1.0 };
+            Impulse {
+                position: position as usize,
+                sign,
+            }
+        })
+        .take_while(move |i| i.position < length)
+}
+
+/// Decorrelation filter convolving its input with exponentially decaying velvet noise, after
+/// Alary, Politis and Välimäki. The sequence is sparse, so the convolution only costs one
+/// multiply-add per impulse. Filters with different seeds give mutually decorrelated outputs of
+/// the same input.
+pub struct VelvetDecorrelator {
+    taps: Vec<(usize, f32)>,
+    line: DelayLine<f32>,
+}
+
+impl VelvetDecorrelator {
+    /// Density of the impulses, per second
+    const DENSITY: f32 = 1500.0;
+    /// Length of the filter, in seconds
+    const LENGTH: f32 = 0.02;
+    /// Decay of the envelope over the length of the filter, in dB
+    const DECAY: f32 = 30.0;
+
+    pub fn new(sample_rate: f32, seed: u32) -> Self {
+        let length = (Self::LENGTH * sample_rate) as usize;
+        let rate = Self::DECAY / 20.0 * std::f32::consts::LN_10 / length.max(1) as f32;
+        let mut taps: Vec<_> = sequence(Self::DENSITY, length, sample_rate, seed)
+            .map(|i| (i.position, i.sign * (-rate * i.position as f32).exp()))
+            .collect();
+        // Unit energy, so that the level stays the same
+        let norm = taps
+            .iter()
+            .map(|(_, g)| g * g)
+            .sum::<f32>()
+            .sqrt()
+            .max(1e-6);
+        taps.iter_mut().for_each(|(_, g)| *g /= norm);
+        Self {
+            taps,
+            line: DelayLine::new(length.max(1)),
+        }
+    }
+}
+
+impl SingleChannelProcess for VelvetDecorrelator {
+    type T = f32;
+
+    #[inline(always)]
+    fn process_single_channel(&mut self, _: &AudioContext, value: f32) -> f32 {
+        self.line.push_pop(value);
+        let last = self.line.len() - 1;
+        self.taps.iter().fold(0.0, |acc, &(position, gain)| {
+            acc + gain * self.line[last - position]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_impulse_per_period() {
+        let impulses: Vec<_> = sequence(1000.0, 48000, 48000.0, 7).collect();
+        assert_eq!(impulses.len(), 1000);
+        for (m, impulse) in impulses.iter().enumerate() {
+            assert_eq!(impulse.position / 48, m);
+        }
+        assert!(impulses.into_iter().eq(sequence(1000.0, 48000, 48000.0, 7)));
+    }
+}
@@ -0,0 +1,79 @@
+use num_traits::Float;
+
+use super::{AudioContext, Process};
+
+/// Which part of a stereo signal is sent through to the processing.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StereoInput {
+    Stereo,
+    Mid,
+    Side,
+}
+
+impl StereoInput {
+    pub fn from_param(value: f32) -> Self {
+        match value.round() as i32 {
+            1 => Self::Mid,
+            2 => Self::Side,
+            _ => Self::Stereo,
+        }
+    }
+}
+
+/// Extracts the mid or side part of a stereo signal, keeping it as a stereo pair so that the side
+/// signal keeps its polarity difference between channels.
+pub struct MidSideInput {
+    pub mode: StereoInput,
+}
+
+impl MidSideInput {
+    pub fn new(mode: StereoInput) -> Self {
+        Self { mode }
+    }
+}
+
+impl Process for MidSideInput {
+    type T = f32;
+    const NIN: usize = 2;
+    const NOUT: usize = 2;
+
+    #[inline(always)]
+    fn process(&mut self, _: &AudioContext, input_frame: &[f32], output_frame: &mut [f32]) {
+        let mid = (input_frame[0] + input_frame[1]) / 2.0;
+        let side = (input_frame[0] - input_frame[1]) / 2.0;
+        let (l, r) = match self.mode {
+            StereoInput::Stereo => (input_frame[0], input_frame[1]),
+            StereoInput::Mid => (mid, mid),
+            StereoInput::Side => (side, -side),
+        };
+        output_frame[0] = l;
+        output_frame[1] = r;
+    }
+}
+
+/// Stereo width control through mid/side scaling; 0 collapses to mono, 1 leaves the signal
+/// untouched, and values above 1 widen the image.
+pub struct Width<T> {
+    pub width: T,
+}
+
+impl<T: Float> Width<T> {
+    pub fn new(width: T) -> Self {
+        Self { width }
+    }
+}
+
+impl<T: audio::Sample + Float> Process for Width<T> {
+    type T = T;
+    const NIN: usize = 2;
+    const NOUT: usize = 2;
+
+    #[inline(always)]
+    fn process(&mut self, _: &AudioContext, input_frame: &[T], output_frame: &mut [T]) {
+        let half = (T::one() + T::one()).recip();
+        let mid = (input_frame[0] + input_frame[1]) * half;
+        let side = (input_frame[0] - input_frame[1]) * half * self.width;
+        output_frame[0] = mid + side;
+        output_frame[1] = mid - side;
+    }
+}
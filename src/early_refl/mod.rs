@@ -0,0 +1,343 @@
+mod patterns;
+
+use crate::{
+    components::{
+        allpass::Allpass,
+        drywet::DryWet,
+        multitap::{MultiTap, Tap},
+        velvet, AudioContext, Process,
+    },
+    room::{Reflection, Room},
+};
+use patterns::ErPattern;
+
+/// Highest number of bounces computed for the image-source model
+const MAX_ORDER: usize = 3;
+/// Longest reflection delay that can be rendered, in seconds
+const MAX_DELAY: f32 = 1.0;
+/// Amount of diffusion applied on top of the reflections of the custom room
+const ROOM_DIFFUSION: f32 = 0.3;
+/// Duration of the crossfade when switching between patterns, in seconds
+const CROSSFADE: f32 = 0.05;
+/// Energy of the velvet noise reflections, in line with the built-in patterns
+const VELVET_ENERGY: f32 = 2.0;
+/// Decay of the velvet noise reflections over their length, in dB
+const VELVET_DECAY: f32 = 40.0;
+/// Highest density of the velvet noise reflections, in impulses per second
+const MAX_VELVET_DENSITY: f32 = 4000.0;
+/// Most taps of a bank, the longest and densest velvet noise being by far the largest table
+const MAX_TAPS: usize = (MAX_VELVET_DENSITY * MAX_DELAY) as usize + 1;
+
+/// Source of the early reflection pattern.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ErType {
+    /// Image-source model from the room geometry parameters
+    Custom,
+    Room,
+    Hall,
+    Chamber,
+    Plate,
+    Studio,
+    /// Sparse random impulses, see `VelvetParams`
+    Velvet,
+}
+
+impl ErType {
+    pub fn from_param(value: f32) -> Self {
+        match value.round() as i32 {
+            1 => Self::Room,
+            2 => Self::Hall,
+            3 => Self::Chamber,
+            4 => Self::Plate,
+            5 => Self::Studio,
+            6 => Self::Velvet,
+            _ => Self::Custom,
+        }
+    }
+
+    fn pattern(self) -> Option<&'static ErPattern> {
+        match self {
+            Self::Custom | Self::Velvet => None,
+            Self::Room => Some(&patterns::ROOM),
+            Self::Hall => Some(&patterns::HALL),
+            Self::Chamber => Some(&patterns::CHAMBER),
+            Self::Plate => Some(&patterns::PLATE),
+            Self::Studio => Some(&patterns::STUDIO),
+        }
+    }
+
+    fn diffusion(self) -> f32 {
+        match self {
+            // Velvet noise is already as dense as it gets
+            Self::Velvet => 0.0,
+            _ => self.pattern().map_or(ROOM_DIFFUSION, |p| p.diffusion),
+        }
+    }
+}
+
+/// Settings of the velvet noise reflections.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VelvetParams {
+    /// Impulses per second
+    pub density: f32,
+    /// Length in seconds
+    pub length: f32,
+    pub seed: u32,
+}
+
+/// Early reflections as discrete taps followed by a short diffuser. The taps either come from an
+/// image-source model of a shoebox room, or from one of the built-in patterns. Any change of the
+/// taps, be it switching patterns or moving the room, loads them into the inactive one of two tap
+/// banks and crossfades to it.
+///
+/// Each reflection is read from, and written back to, the line matching its direction of arrival.
+pub struct EarlyReflections<const N: usize> {
+    sample_rate: f32,
+    er_type: ErType,
+    /// Type of the taps loaded in each bank
+    bank_types: [ErType; 2],
+    room: Option<Room>,
+    velvet: Option<VelvetParams>,
+    reflections: Vec<Reflection>,
+    /// Taps of the velvet noise, filled in place when loaded
+    velvet_taps: Vec<Tap>,
+    banks: [MultiTap<N, N>; 2],
+    active: usize,
+    fade: f32,
+    /// Whether the taps have changed since the last load, waiting for the current fade to end
+    pending: bool,
+    time_scale: f32,
+    diffuser: DryWet<Allpass<N>, N>,
+}
+
+impl<const N: usize> EarlyReflections<N> {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut diffuser = DryWet::new(Allpass::new((sample_rate * 0.05) as _));
+        diffuser.set_amount(ROOM_DIFFUSION);
+        Self {
+            sample_rate,
+            er_type: ErType::Custom,
+            bank_types: [ErType::Custom; 2],
+            room: None,
+            velvet: None,
+            // Enough for every image the search goes through, so that moving the room doesn't
+            // allocate either
+            reflections: Vec::with_capacity((2 * MAX_ORDER + 1).pow(3) * 8),
+            velvet_taps: Vec::with_capacity(MAX_TAPS),
+            banks: [
+                MultiTap::new(sample_rate, MAX_DELAY, MAX_TAPS),
+                MultiTap::new(sample_rate, MAX_DELAY, MAX_TAPS),
+            ],
+            active: 0,
+            fade: 1.0,
+            pending: false,
+            time_scale: 1.0,
+            diffuser,
+        }
+    }
+
+    /// Scales the diffuser, and the tap times of the built-in patterns (a fraction of 0.5 plays
+    /// them at their nominal times). Custom rooms are scaled through their dimensions instead,
+    /// see `size_scale`, and velvet noise keeps its own length.
+    pub fn set_delay_fract(&mut self, f: f32) {
+        self.diffuser.process.update(f);
+        self.time_scale = size_scale(f);
+        for (bank, t) in self.banks.iter_mut().zip(self.bank_types.iter()) {
+            bank.set_time_scale(if t.pattern().is_some() {
+                self.time_scale
+            } else {
+                1.0
+            });
+        }
+    }
+
+    pub fn set_type(&mut self, er_type: ErType) {
+        if er_type == self.er_type {
+            return;
+        }
+        self.er_type = er_type;
+        self.reload();
+    }
+
+    pub fn set_room(&mut self, room: Room) {
+        if self.room == Some(room) {
+            return;
+        }
+        self.room = Some(room);
+        room.reflections(MAX_ORDER, &mut self.reflections);
+        if self.er_type == ErType::Custom {
+            self.reload();
+        }
+    }
+
+    pub fn set_velvet(&mut self, velvet: VelvetParams) {
+        if self.velvet == Some(velvet) {
+            return;
+        }
+        self.velvet = Some(velvet);
+        if self.er_type == ErType::Velvet {
+            self.reload();
+        }
+    }
+
+    /// Crossfades to the current taps, once the fade in progress, if any, has ended so that the
+    /// bank fading out is never cut off.
+    fn reload(&mut self) {
+        self.pending = true;
+        if self.fade < 1.0 {
+            return;
+        }
+        self.pending = false;
+        self.active = 1 - self.active;
+        self.fade = 0.0;
+        self.load_active();
+    }
+
+    fn load_active(&mut self) {
+        self.bank_types[self.active] = self.er_type;
+        let bank = &mut self.banks[self.active];
+        match self.er_type.pattern() {
+            Some(pattern) => {
+                bank.set_time_scale(self.time_scale);
+                bank.load(pattern.taps.iter().map(|&(time, gain, line)| Tap {
+                    delay: time / 1000.0,
+                    gain,
+                    input: line % N,
+                    output: line % N,
+                    cutoff: pattern.cutoff(time / 1000.0),
+                }))
+            }
+            None if self.er_type == ErType::Velvet => {
+                bank.set_time_scale(1.0);
+                let params = self.velvet.unwrap_or(VelvetParams {
+                    density: 1000.0,
+                    length: 0.08,
+                    seed: 0,
+                });
+                velvet_taps(params, N, self.sample_rate, &mut self.velvet_taps);
+                bank.load(self.velvet_taps.iter().copied())
+            }
+            None => {
+                bank.set_time_scale(1.0);
+                bank.load(self.reflections.iter().map(|r| Tap {
+                    delay: r.delay.min(MAX_DELAY),
+                    gain: r.gain,
+                    input: r.line,
+                    output: r.line,
+                    cutoff: Some(r.cutoff),
+                }))
+            }
+        }
+    }
+}
+
+/// Scale of the early reflection times for an ER size of `f`, 0.5 giving the nominal times.
+pub fn size_scale(f: f32) -> f32 {
+    (2.0 * f).max(0.05)
+}
+
+/// Reflections from exponentially decaying velvet noise, dealt over `lines` lines, replacing the
+/// contents of `out`.
+fn velvet_taps(params: VelvetParams, lines: usize, sample_rate: f32, out: &mut Vec<Tap>) {
+    let length = params.length.max(1e-3).min(MAX_DELAY);
+    let impulses = velvet::sequence(
+        params.density.min(MAX_VELVET_DENSITY),
+        (length * sample_rate) as usize,
+        sample_rate,
+        params.seed,
+    );
+    let rate = VELVET_DECAY / 20.0 * std::f32::consts::LN_10 / length;
+    out.clear();
+    out.extend(impulses.take(MAX_TAPS).enumerate().map(|(k, i)| {
+        let delay = i.position as f32 / sample_rate;
+        Tap {
+            delay,
+            gain: i.sign * (-rate * delay).exp(),
+            input: k % lines,
+            output: k % lines,
+            cutoff: None,
+        }
+    }));
+    let energy = out.iter().map(|t| t.gain * t.gain).sum::<f32>().max(1e-6);
+    let norm = (VELVET_ENERGY / energy).sqrt();
+    out.iter_mut().for_each(|t| t.gain *= norm);
+}
+
+impl<const N: usize> Process for EarlyReflections<N> {
+    type T = f32;
+    const NIN: usize = N;
+    const NOUT: usize = N;
+
+    #[inline(always)]
+    fn process(
+        &mut self,
+        ctx: &AudioContext,
+        input_frame: &[Self::T],
+        output_frame: &mut [Self::T],
+    ) {
+        // Both banks always run so that their delay lines are up to date when switching
+        let mut reflections = [0.0; N];
+        let mut previous = [0.0; N];
+        self.banks[self.active].process(ctx, input_frame, &mut reflections);
+        self.banks[1 - self.active].process(ctx, input_frame, &mut previous);
+
+        if self.fade < 1.0 {
+            for (x, p) in reflections.iter_mut().zip(previous.iter()) {
+                *x = *x * self.fade + p * (1.0 - self.fade);
+            }
+            let diffusion = self.bank_types[self.active].diffusion() * self.fade
+                + self.bank_types[1 - self.active].diffusion() * (1.0 - self.fade);
+            self.diffuser.set_amount(diffusion);
+            self.fade = (self.fade + 1.0 / (CROSSFADE * self.sample_rate)).min(1.0);
+        } else {
+            self.diffuser
+                .set_amount(self.bank_types[self.active].diffusion());
+            if self.pending {
+                self.reload();
+            }
+        }
+        self.diffuser.process(ctx, &reflections, output_frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(width: f32) -> Room {
+        Room::from_params([width, 8.0, 3.0], [0.3, 0.7], [0.5, 0.3], 0.3, 0.3, 0.3)
+    }
+
+    #[test]
+    fn test_room_change_crossfades() {
+        let ctx = AudioContext::new(48000.0);
+        let mut er = EarlyReflections::<8>::new(48000.0);
+        er.set_room(room(6.0));
+        let first = er.active;
+        // A change during the fade waits for it to end instead of cutting off the old taps
+        er.set_room(room(7.0));
+        assert_eq!(er.active, first);
+        assert!(er.pending);
+        let mut out = [0.0; 8];
+        for _ in 0..(CROSSFADE * 48000.0) as usize + 1 {
+            er.process(&ctx, &[0.0; 8], &mut out);
+        }
+        assert_ne!(er.active, first);
+        assert!(!er.pending);
+        assert!(er.fade < 1.0);
+    }
+
+    #[test]
+    fn test_densest_velvet_fits_the_banks() {
+        let mut taps = Vec::with_capacity(MAX_TAPS);
+        let buffer = taps.as_ptr();
+        let params = VelvetParams {
+            density: MAX_VELVET_DENSITY,
+            length: MAX_DELAY,
+            seed: 3,
+        };
+        velvet_taps(params, 8, 48000.0, &mut taps);
+        assert!(taps.len() > MAX_TAPS - 2);
+        assert_eq!(taps.as_ptr(), buffer);
+    }
+}
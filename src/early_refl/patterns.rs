@@ -0,0 +1,136 @@
+/// Built-in early reflection pattern. Taps are given as (time in ms, gain, line), with even lines
+/// on the left and odd lines on the right.
+pub struct ErPattern {
+    pub taps: &'static [(f32, f32, usize)],
+    /// Amount of diffusion applied after the taps
+    pub diffusion: f32,
+    /// Rate at which later taps get darker; the cutoff of a tap at time t is 20 kHz * exp(-damping * t)
+    pub damping: f32,
+}
+
+impl ErPattern {
+    pub fn cutoff(&self, time: f32) -> Option<f32> {
+        if self.damping > 0.0 {
+            Some(20000.0 * (-self.damping * time).exp())
+        } else {
+            None
+        }
+    }
+}
+
+/// Small, lively room: strong, closely spaced reflections
+pub const ROOM: ErPattern = ErPattern {
+    taps: &[
+        (4.3, 0.84, 0),
+        (5.9, 0.78, 1),
+        (8.7, 0.66, 2),
+        (10.1, 0.63, 5),
+        (12.8, 0.52, 4),
+        (14.2, 0.55, 3),
+        (17.5, 0.41, 6),
+        (19.1, 0.44, 7),
+        (23.6, 0.33, 0),
+        (25.2, 0.31, 3),
+        (29.9, 0.24, 2),
+        (31.4, 0.26, 1),
+        (36.8, 0.18, 4),
+        (39.7, 0.17, 7),
+    ],
+    diffusion: 0.4,
+    damping: 10.0,
+};
+
+/// Large concert hall: late first reflections from distant walls, thickening over time
+pub const HALL: ErPattern = ErPattern {
+    taps: &[
+        (17.0, 0.62, 1),
+        (21.4, 0.58, 0),
+        (29.8, 0.49, 2),
+        (34.1, 0.51, 3),
+        (41.5, 0.42, 5),
+        (47.2, 0.40, 4),
+        (55.9, 0.35, 6),
+        (58.3, 0.36, 7),
+        (66.0, 0.30, 1),
+        (71.8, 0.29, 2),
+        (77.1, 0.27, 0),
+        (83.5, 0.25, 3),
+        (89.0, 0.22, 5),
+        (95.6, 0.21, 6),
+        (102.3, 0.18, 4),
+        (109.9, 0.17, 7),
+        (117.2, 0.14, 1),
+        (124.0, 0.13, 2),
+    ],
+    diffusion: 0.6,
+    damping: 6.0,
+};
+
+/// Reverberation chamber: hard walls and a dense pattern right from the start
+pub const CHAMBER: ErPattern = ErPattern {
+    taps: &[
+        (5.1, 0.71, 0),
+        (6.3, 0.69, 3),
+        (9.4, 0.64, 1),
+        (11.0, 0.62, 6),
+        (13.7, 0.58, 5),
+        (15.2, 0.57, 2),
+        (18.9, 0.52, 7),
+        (21.3, 0.50, 4),
+        (24.8, 0.46, 0),
+        (27.5, 0.45, 1),
+        (31.2, 0.41, 2),
+        (34.0, 0.40, 3),
+        (38.6, 0.36, 6),
+        (41.9, 0.35, 7),
+        (46.3, 0.31, 4),
+        (50.7, 0.30, 5),
+        (57.4, 0.26, 0),
+        (63.1, 0.24, 1),
+        (70.2, 0.21, 2),
+        (78.8, 0.19, 3),
+    ],
+    diffusion: 0.7,
+    damping: 12.0,
+};
+
+/// Plate: no discrete echoes, a bright and immediate build-up
+pub const PLATE: ErPattern = ErPattern {
+    taps: &[
+        (1.1, 0.58, 0),
+        (1.7, 0.57, 1),
+        (2.6, 0.55, 2),
+        (3.2, 0.55, 3),
+        (4.3, 0.53, 4),
+        (5.0, 0.52, 5),
+        (6.4, 0.50, 6),
+        (7.1, 0.49, 7),
+        (8.8, 0.47, 1),
+        (9.9, 0.46, 0),
+        (12.0, 0.43, 3),
+        (13.6, 0.42, 2),
+        (16.1, 0.39, 5),
+        (18.4, 0.37, 4),
+        (21.9, 0.34, 7),
+        (25.3, 0.31, 6),
+        (29.6, 0.28, 1),
+    ],
+    diffusion: 0.9,
+    damping: 0.0,
+};
+
+/// Treated studio live room: a few short, damped reflections
+pub const STUDIO: ErPattern = ErPattern {
+    taps: &[
+        (2.4, 0.52, 1),
+        (3.9, 0.47, 0),
+        (6.8, 0.36, 2),
+        (8.2, 0.33, 3),
+        (12.5, 0.22, 5),
+        (15.3, 0.19, 4),
+        (20.7, 0.11, 6),
+        (24.9, 0.09, 7),
+    ],
+    diffusion: 0.2,
+    damping: 20.0,
+};
@@ -0,0 +1,133 @@
+use crate::components::{
+    comb::DampedComb, schroeder::SchroederAllpass, AudioContext, Process, SingleChannelProcess,
+};
+use crate::layout::StereoLines;
+
+/// Sample rate the delays of the original tuning are given at
+const REFERENCE_RATE: f32 = 44100.0;
+/// Comb delays of the left channel, in samples at the reference rate
+const COMBS: [f32; 8] = [
+    1116.0, 1188.0, 1277.0, 1356.0, 1422.0, 1491.0, 1557.0, 1617.0,
+];
+/// Allpass delays of the left channel, in samples at the reference rate
+const ALLPASSES: [f32; 4] = [556.0, 441.0, 341.0, 225.0];
+/// Extra delay of the right channel, in samples at the reference rate
+const STEREO_SPREAD: f32 = 23.0;
+/// Gain of the allpasses
+const ALLPASS_GAIN: f32 = 0.5;
+/// Input gain, compensating for the summed resonances of the combs
+const FIXED_GAIN: f32 = 0.015;
+/// Scale applied to the delays at the largest size
+const MAX_SCALE: f32 = 1.5;
+
+struct Channel {
+    combs: Vec<DampedComb<f32>>,
+    allpasses: Vec<SchroederAllpass<f32>>,
+    delays: Vec<f32>,
+}
+
+impl Channel {
+    fn new(sample_rate: f32, spread: f32) -> Self {
+        let rate = sample_rate / REFERENCE_RATE;
+        let len = |d: f32| ((d + spread) * rate * MAX_SCALE).ceil() as usize + 1;
+        Self {
+            combs: COMBS
+                .iter()
+                .map(|&d| DampedComb::new(len(d), (d + spread) * rate, 0.84))
+                .collect(),
+            allpasses: ALLPASSES
+                .iter()
+                .map(|&d| SchroederAllpass::new(len(d), (d + spread) * rate, ALLPASS_GAIN))
+                .collect(),
+            delays: COMBS.iter().map(|&d| (d + spread) * rate).collect(),
+        }
+    }
+
+    fn process(&mut self, ctx: &AudioContext, value: f32) -> f32 {
+        let mut x = self.combs.iter_mut().fold(0.0, |acc, comb| {
+            acc + comb.process_single_channel(ctx, value)
+        });
+        for allpass in &mut self.allpasses {
+            x = allpass.process_single_channel(ctx, x);
+        }
+        x
+    }
+
+    fn reset(&mut self) {
+        self.combs.iter_mut().for_each(|c| c.reset_single_channel());
+        self.allpasses
+            .iter_mut()
+            .for_each(|a| a.reset_single_channel());
+    }
+}
+
+/// Freeverb, Jezar's public domain Schroeder/Moorer reverberator: eight parallel damped combs
+/// followed by four series allpasses per channel, the right channel being tuned slightly longer
+/// than the left one.
+///
+/// The lines come in and go out through `StereoLines`, the input being summed to mono.
+pub(crate) struct Freeverb<const N: usize> {
+    sample_rate: f32,
+    lines: StereoLines<N>,
+    channels: [Channel; 2],
+}
+
+impl<const N: usize> Freeverb<N> {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            lines: StereoLines::new(),
+            channels: [0.0, STEREO_SPREAD].map(|s| Channel::new(sample_rate, s)),
+        }
+    }
+
+    /// Scales the comb delays, a size of 0.5 giving the original tuning.
+    pub fn update_size(&mut self, size: f32) {
+        let scale = (0.5 + size).max(0.5).min(MAX_SCALE);
+        for channel in &mut self.channels {
+            for (comb, delay) in channel.combs.iter_mut().zip(channel.delays.iter()) {
+                comb.set_delay(delay * scale);
+            }
+        }
+    }
+
+    /// Maps the decay to the original room size range of the comb feedback.
+    pub fn update_feedback(&mut self, feedback: f32) {
+        let feedback = 0.7 + 0.28 * feedback.max(0.0).min(1.0);
+        for channel in &mut self.channels {
+            channel.combs.iter_mut().for_each(|c| c.feedback = feedback);
+        }
+    }
+
+    pub fn update_damping(&mut self, cutoff: f32) {
+        for channel in &mut self.channels {
+            for comb in &mut channel.combs {
+                comb.set_damping(self.sample_rate, cutoff);
+            }
+        }
+    }
+
+    /// Follows the decorrelation of the plugin mixers, see `StereoLines`.
+    pub fn update_decorrelation(&mut self, decorrelation: f32) {
+        self.lines.set_decorrelation(decorrelation);
+    }
+}
+
+impl<const N: usize> Process for Freeverb<N> {
+    type T = f32;
+    const NIN: usize = N;
+    const NOUT: usize = N;
+
+    fn process(&mut self, ctx: &AudioContext, input_frame: &[f32], output_frame: &mut [f32]) {
+        let mut stereo = self.lines.downmix(ctx, input_frame);
+        let x = (stereo[0] + stereo[1]) * FIXED_GAIN;
+        for (y, channel) in stereo.iter_mut().zip(self.channels.iter_mut()) {
+            *y = channel.process(ctx, x);
+        }
+        self.lines.upmix(ctx, &stereo, output_frame);
+    }
+
+    fn reset(&mut self) {
+        self.channels.iter_mut().for_each(|c| c.reset());
+    }
+}
@@ -0,0 +1,276 @@
+use crate::components::{
+    delay::DelayLine, feedback::Feedback, filter::DampedGain, nested::NestedAllpass,
+    schroeder::SchroederAllpass, seq::Sequence, AudioContext, Process, SingleChannelProcess,
+};
+use crate::layout::StereoLines;
+
+/// Scale applied to the delays at the largest size
+const MAX_SCALE: f32 = 1.5;
+/// Delay scale of the right channel, to decorrelate it from the left one
+const DETUNE: [f32; 2] = [1.0, 1.07];
+
+/// Element of a room topology, with delays in milliseconds.
+#[derive(Copy, Clone)]
+enum Section {
+    Delay(f32),
+    /// Allpass of the given delay and gain
+    Allpass(f32, f32),
+    /// Allpass of the given delay and gain, containing one or two allpasses in series
+    Nested(f32, f32, &'static [(f32, f32)]),
+    /// Output tap with the given gain
+    Tap(f32),
+}
+
+/// Room topology from Gardner's "A Realtime Multichannel Room Simulator".
+pub(crate) struct GardnerRoom {
+    sections: &'static [Section],
+    /// Cutoff of the lowpass filter in the outer feedback loop, in Hz
+    cutoff: f32,
+}
+
+pub(crate) const SMALL: GardnerRoom = GardnerRoom {
+    sections: &[
+        Section::Nested(35.0, 0.15, &[(22.0, 0.4), (8.3, 0.6)]),
+        Section::Tap(0.5),
+        Section::Nested(66.0, 0.08, &[(30.0, 0.3)]),
+        Section::Tap(0.5),
+    ],
+    cutoff: 4200.0,
+};
+
+pub(crate) const MEDIUM: GardnerRoom = GardnerRoom {
+    sections: &[
+        Section::Nested(35.0, 0.25, &[(8.3, 0.7), (22.0, 0.5)]),
+        Section::Tap(0.5),
+        Section::Allpass(5.0, 0.7),
+        Section::Delay(67.0),
+        Section::Tap(0.5),
+        Section::Delay(15.0),
+        Section::Nested(39.0, 0.3, &[(9.8, 0.6)]),
+        Section::Tap(0.5),
+        Section::Delay(108.0),
+    ],
+    cutoff: 2500.0,
+};
+
+pub(crate) const LARGE: GardnerRoom = GardnerRoom {
+    sections: &[
+        Section::Allpass(8.0, 0.3),
+        Section::Allpass(12.0, 0.3),
+        Section::Delay(4.0),
+        Section::Tap(0.34),
+        Section::Delay(17.0),
+        Section::Nested(87.0, 0.5, &[(62.0, 0.25)]),
+        Section::Delay(31.0),
+        Section::Tap(0.14),
+        Section::Delay(3.0),
+        Section::Nested(120.0, 0.5, &[(76.0, 0.25), (30.0, 0.25)]),
+        Section::Tap(0.14),
+    ],
+    cutoff: 2600.0,
+};
+
+type Single = SchroederAllpass<f32>;
+
+enum Element {
+    Delay(DelayLine<f32>),
+    Allpass(Single),
+    Nested(NestedAllpass<f32, Single>),
+    DoubleNested(NestedAllpass<f32, Sequence<f32, Single, Single>>),
+    Tap(f32),
+}
+
+impl Element {
+    fn new(section: Section, sample_rate: f32) -> Self {
+        let samples = |ms: f32| ms / 1000.0 * sample_rate;
+        let len = |ms: f32| (samples(ms) * MAX_SCALE * DETUNE[1]).ceil() as usize + 1;
+        let allpass = |(ms, gain): (f32, f32)| Single::new(len(ms), samples(ms), gain);
+        match section {
+            Section::Delay(ms) => Self::Delay(DelayLine::new(len(ms))),
+            Section::Allpass(ms, gain) => Self::Allpass(allpass((ms, gain))),
+            Section::Nested(ms, gain, &[inner]) => Self::Nested(NestedAllpass::new(
+                len(ms),
+                samples(ms),
+                gain,
+                allpass(inner),
+            )),
+            Section::Nested(ms, gain, inner) => Self::DoubleNested(NestedAllpass::new(
+                len(ms),
+                samples(ms),
+                gain,
+                Sequence::new(allpass(inner[0]), allpass(inner[1])),
+            )),
+            Section::Tap(gain) => Self::Tap(gain),
+        }
+    }
+}
+
+/// Chain of sections of one channel, outputting the end of the chain for the outer feedback loop.
+/// The sum of the taps along the way is kept in `output`.
+struct Chain {
+    sections: &'static [Section],
+    elements: Vec<Element>,
+    sample_rate: f32,
+    scale: f32,
+    output: f32,
+}
+
+impl Chain {
+    fn new(room: &GardnerRoom, sample_rate: f32) -> Self {
+        Self {
+            sections: room.sections,
+            elements: room
+                .sections
+                .iter()
+                .map(|&s| Element::new(s, sample_rate))
+                .collect(),
+            sample_rate,
+            scale: 1.0,
+            output: 0.0,
+        }
+    }
+
+    fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+        let rate = self.sample_rate * scale / 1000.0;
+        let samples = |ms: f32| ms * rate;
+        for (element, section) in self.elements.iter_mut().zip(self.sections.iter()) {
+            match (element, *section) {
+                (Element::Allpass(ap), Section::Allpass(ms, _)) => ap.set_delay(samples(ms)),
+                (Element::Nested(ap), Section::Nested(ms, _, inner)) => {
+                    ap.set_delay(samples(ms));
+                    ap.inner.set_delay(samples(inner[0].0));
+                }
+                (Element::DoubleNested(ap), Section::Nested(ms, _, inner)) => {
+                    ap.set_delay(samples(ms));
+                    ap.inner.pa.set_delay(samples(inner[0].0));
+                    ap.inner.pb.set_delay(samples(inner[1].0));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl SingleChannelProcess for Chain {
+    type T = f32;
+
+    fn process_single_channel(&mut self, ctx: &AudioContext, value: f32) -> f32 {
+        let rate = self.sample_rate * self.scale / 1000.0;
+        let samples = |ms: f32| ms * rate;
+        let mut x = value;
+        self.output = 0.0;
+        for (element, section) in self.elements.iter_mut().zip(self.sections.iter()) {
+            x = match element {
+                Element::Delay(line) => {
+                    line.push_pop(x);
+                    match section {
+                        Section::Delay(ms) => line.read(samples(*ms)),
+                        _ => x,
+                    }
+                }
+                Element::Allpass(ap) => ap.process_single_channel(ctx, x),
+                Element::Nested(ap) => ap.process_single_channel(ctx, x),
+                Element::DoubleNested(ap) => ap.process_single_channel(ctx, x),
+                Element::Tap(gain) => {
+                    self.output += *gain * x;
+                    x
+                }
+            };
+        }
+        x
+    }
+
+    fn reset_single_channel(&mut self) {
+        for element in &mut self.elements {
+            match element {
+                Element::Delay(line) => line.clear(),
+                Element::Allpass(ap) => ap.reset_single_channel(),
+                Element::Nested(ap) => ap.reset_single_channel(),
+                Element::DoubleNested(ap) => ap.reset_single_channel(),
+                Element::Tap(_) => {}
+            }
+        }
+        self.output = 0.0;
+    }
+}
+
+/// Gardner's nested allpass room reverberators: a chain of allpasses, nested allpasses and delays
+/// in a lowpassed outer feedback loop, with the output taken from taps along the chain. Each
+/// stereo channel runs its own chain, the right one slightly longer.
+///
+/// The lines come in and go out through `StereoLines`, both chains being fed the mono sum.
+pub(crate) struct Gardner<const N: usize> {
+    sample_rate: f32,
+    cutoff: f32,
+    lines: StereoLines<N>,
+    channels: [Feedback<Chain, DampedGain<f32>, 1>; 2],
+}
+
+impl<const N: usize> Gardner<N> {
+    pub fn new(sample_rate: f32, room: &GardnerRoom) -> Self {
+        let mut gardner = Self {
+            sample_rate,
+            cutoff: room.cutoff,
+            lines: StereoLines::new(),
+            channels: DETUNE.map(|d| {
+                let mut chain = Chain::new(room, sample_rate);
+                chain.set_scale(d);
+                Feedback::new(chain, DampedGain::new(0.5))
+            }),
+        };
+        gardner.update_damping(f32::INFINITY);
+        gardner
+    }
+
+    /// Scales the delays, a size of 0.5 giving the original topology.
+    pub fn update_size(&mut self, size: f32) {
+        let scale = (0.5 + size).max(0.5).min(MAX_SCALE);
+        for (channel, detune) in self.channels.iter_mut().zip(DETUNE.iter()) {
+            channel.forward_mut().set_scale(scale * detune);
+        }
+    }
+
+    pub fn update_feedback(&mut self, feedback: f32) {
+        for channel in &mut self.channels {
+            channel.backward_mut().gain = feedback.max(0.0).min(1.0);
+        }
+    }
+
+    /// Sets the damping of the outer loop, which never goes above the cutoff of the room.
+    pub fn update_damping(&mut self, cutoff: f32) {
+        let cutoff = cutoff.min(self.cutoff);
+        for channel in &mut self.channels {
+            channel
+                .backward_mut()
+                .damping
+                .set_cutoff(self.sample_rate, cutoff);
+        }
+    }
+
+    /// Follows the decorrelation of the plugin mixers, see `StereoLines`.
+    pub fn update_decorrelation(&mut self, decorrelation: f32) {
+        self.lines.set_decorrelation(decorrelation);
+    }
+}
+
+impl<const N: usize> Process for Gardner<N> {
+    type T = f32;
+    const NIN: usize = N;
+    const NOUT: usize = N;
+
+    fn process(&mut self, ctx: &AudioContext, input_frame: &[f32], output_frame: &mut [f32]) {
+        let mut stereo = self.lines.downmix(ctx, input_frame);
+        let x = [0.5 * (stereo[0] + stereo[1])];
+        for (y, channel) in stereo.iter_mut().zip(self.channels.iter_mut()) {
+            let mut end = [0.0];
+            channel.process(ctx, &x, &mut end);
+            *y = channel.forward_mut().output;
+        }
+        self.lines.upmix(ctx, &stereo, output_frame);
+    }
+
+    fn reset(&mut self) {
+        self.channels.iter_mut().for_each(|c| c.reset());
+    }
+}
@@ -0,0 +1,264 @@
+//! Channel layout of the plugin, selected at build time through the `layout-*` cargo features.
+//! Stereo in/out is used when no layout feature is enabled.
+//!
+//! Speaker orders follow the usual VST conventions:
+//! - quad: L R Ls Rs
+//! - 5.1: L R C LFE Ls Rs
+//! - 7.1: L R C LFE Ls Rs Lrs Rrs
+//!
+//! Ambisonic layouts (`layout-foa` for first order, `layout-toa` for third order) take a stereo
+//! input and output AmbiX (ACN ordering, SN3D normalization) B-format.
+//!
+//! The `layout-binaural` layout is stereo in/out for headphones, with the reverb lines rendered
+//! through HRIRs instead of being mixed down to stereo.
+
+use crate::components::{
+    ambisonics::{self, AmbisonicEncoder},
+    binaural::Binaural,
+    mixer::Mixer,
+    AudioContext, Process,
+};
+
+const _: () = assert!(
+    cfg!(feature = "layout-mono") as usize
+        + cfg!(feature = "layout-quad") as usize
+        + cfg!(feature = "layout-5-1") as usize
+        + cfg!(feature = "layout-7-1") as usize
+        + cfg!(feature = "layout-foa") as usize
+        + cfg!(feature = "layout-toa") as usize
+        + cfg!(feature = "layout-binaural") as usize
+        <= 1,
+    "Only one `layout-*` feature can be enabled at a time"
+);
+
+#[cfg(feature = "layout-mono")]
+mod current {
+    pub const INPUTS: usize = 1;
+    pub const OUTPUTS: usize = 2;
+    pub const LFE: Option<usize> = None;
+    pub const AMBISONICS: bool = false;
+}
+
+#[cfg(feature = "layout-quad")]
+mod current {
+    pub const INPUTS: usize = 4;
+    pub const OUTPUTS: usize = 4;
+    pub const LFE: Option<usize> = None;
+    pub const AMBISONICS: bool = false;
+}
+
+#[cfg(feature = "layout-5-1")]
+mod current {
+    pub const INPUTS: usize = 6;
+    pub const OUTPUTS: usize = 6;
+    pub const LFE: Option<usize> = Some(3);
+    pub const AMBISONICS: bool = false;
+}
+
+#[cfg(feature = "layout-7-1")]
+mod current {
+    pub const INPUTS: usize = 8;
+    pub const OUTPUTS: usize = 8;
+    pub const LFE: Option<usize> = Some(3);
+    pub const AMBISONICS: bool = false;
+}
+
+#[cfg(feature = "layout-foa")]
+mod current {
+    pub const INPUTS: usize = 2;
+    pub const OUTPUTS: usize = crate::components::ambisonics::channel_count(1);
+    pub const LFE: Option<usize> = None;
+    pub const AMBISONICS: bool = true;
+}
+
+#[cfg(feature = "layout-toa")]
+mod current {
+    pub const INPUTS: usize = 2;
+    pub const OUTPUTS: usize = crate::components::ambisonics::channel_count(3);
+    pub const LFE: Option<usize> = None;
+    pub const AMBISONICS: bool = true;
+}
+
+#[cfg(feature = "layout-binaural")]
+mod current {
+    pub const INPUTS: usize = 2;
+    pub const OUTPUTS: usize = 2;
+    pub const LFE: Option<usize> = None;
+    pub const AMBISONICS: bool = false;
+}
+
+#[cfg(not(any(
+    feature = "layout-mono",
+    feature = "layout-quad",
+    feature = "layout-5-1",
+    feature = "layout-7-1",
+    feature = "layout-foa",
+    feature = "layout-toa",
+    feature = "layout-binaural"
+)))]
+mod current {
+    pub const INPUTS: usize = 2;
+    pub const OUTPUTS: usize = 2;
+    pub const LFE: Option<usize> = None;
+    pub const AMBISONICS: bool = false;
+}
+
+pub use current::*;
+
+/// Whether the output is binaural, in which case stereo processing after the output stage would
+/// destroy the interaural cues.
+pub const BINAURAL: bool = cfg!(feature = "layout-binaural");
+
+/// Stage rendering the 8 reverb lines to the output channels of the layout.
+pub(crate) trait OutputStage: Process<T = f32> {
+    fn new(sample_rate: f32, decorrelation: f32) -> Self;
+    fn set_decorrelation(&mut self, decorrelation: f32);
+}
+
+impl<const OUT: usize> OutputStage for Mixer<f32, 8, OUT> {
+    fn new(_: f32, decorrelation: f32) -> Self {
+        Mixer::new(decorrelation)
+    }
+
+    fn set_decorrelation(&mut self, decorrelation: f32) {
+        Mixer::set_decorrelation(self, decorrelation)
+    }
+}
+
+impl<const OUT: usize> OutputStage for AmbisonicEncoder<8, OUT> {
+    fn new(_: f32, _: f32) -> Self {
+        AmbisonicEncoder::new(&ambisonics::cube_directions())
+    }
+
+    // Lines are already spread in space and decorrelated by the reverb itself
+    fn set_decorrelation(&mut self, _: f32) {}
+}
+
+impl OutputStage for Binaural<8> {
+    fn new(sample_rate: f32, _: f32) -> Self {
+        Binaural::spherical_head(sample_rate, &ambisonics::cube_directions())
+    }
+
+    fn set_decorrelation(&mut self, _: f32) {}
+}
+
+#[cfg(not(any(
+    feature = "layout-foa",
+    feature = "layout-toa",
+    feature = "layout-binaural"
+)))]
+pub(crate) type Fanin = Mixer<f32, 8, OUTPUTS>;
+#[cfg(any(feature = "layout-foa", feature = "layout-toa"))]
+pub(crate) type Fanin = AmbisonicEncoder<8, OUTPUTS>;
+#[cfg(feature = "layout-binaural")]
+pub(crate) type Fanin = Binaural<8>;
+
+/// Channels the output stage gathers from the lines with a `Mixer`: the output channels of
+/// speaker layouts. Ambisonic and binaural stages place the lines in space instead, which stereo
+/// is spread to directly.
+const MIXED_OUTPUTS: usize = if AMBISONICS || BINAURAL { 2 } else { OUTPUTS };
+
+/// Stereo input and output of the engines that don't work on lines.
+///
+/// The plugin spreads its inputs over the lines and gathers them back to its outputs with mixers
+/// that alternate polarities. Folding the lines to stereo with any other matrix partly cancels
+/// them, so the lines are folded with the transpose of the fanout and spread with the transpose of
+/// the output mixer, at the same decorrelation. Channels are then paired with stereo by index,
+/// even channels on the left and odd channels on the right.
+pub(crate) struct StereoLines<const N: usize> {
+    input: Mixer<f32, N, INPUTS>,
+    output: Mixer<f32, MIXED_OUTPUTS, N>,
+}
+
+impl<const N: usize> StereoLines<N> {
+    pub fn new() -> Self {
+        Self {
+            input: Mixer::transposed(0.0),
+            output: Mixer::transposed(0.0),
+        }
+    }
+
+    /// Follows the decorrelation of the fanout and output mixers of the plugin.
+    pub fn set_decorrelation(&mut self, decorrelation: f32) {
+        self.input.set_decorrelation(decorrelation);
+        self.output.set_decorrelation(decorrelation);
+    }
+
+    pub fn downmix(&mut self, ctx: &AudioContext, lines: &[f32]) -> [f32; 2] {
+        let mut channels = [0.0; INPUTS];
+        self.input.process(ctx, lines, &mut channels);
+        if INPUTS == 1 {
+            return [channels[0]; 2];
+        }
+        // Normalized like the mixer, keeping the level of uncorrelated channels
+        let gain = (((INPUTS + 1) / 2) as f32).sqrt().recip();
+        let mut stereo = [0.0; 2];
+        for (c, x) in channels.iter().enumerate() {
+            stereo[c % 2] += gain * x;
+        }
+        stereo
+    }
+
+    pub fn upmix(&mut self, ctx: &AudioContext, stereo: &[f32; 2], lines: &mut [f32]) {
+        let gain = (((MIXED_OUTPUTS + 1) / 2) as f32).sqrt().recip();
+        let mut channels = [0.0; MIXED_OUTPUTS];
+        for (c, x) in channels.iter_mut().enumerate() {
+            *x = gain * stereo[c % 2];
+        }
+        self.output.process(ctx, &channels, lines);
+    }
+}
+
+/// Gains of the dry signal from each input channel (inner index) to each output channel. Mono
+/// inputs are sent to every speaker, speaker layouts are passed through one to one, and stereo
+/// inputs are encoded at ±30° for ambisonic layouts.
+pub fn dry_matrix() -> [[f32; INPUTS]; OUTPUTS] {
+    let mut matrix = [[0.0; INPUTS]; OUTPUTS];
+    for (c, row) in matrix.iter_mut().enumerate() {
+        for (j, gain) in row.iter_mut().enumerate() {
+            *gain = if AMBISONICS {
+                let azimuth = if j == 0 { 30f32 } else { -30f32 }.to_radians();
+                ambisonics::sn3d(azimuth, 0.0)[c]
+            } else if INPUTS == 1 || c == j {
+                1.0
+            } else {
+                0.0
+            };
+        }
+    }
+    matrix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stereo_lines_undo_the_plugin_mixers() {
+        let ctx = AudioContext::new(48000.0);
+        for &d in &[0.0, 0.5, 1.0] {
+            let mut lines = StereoLines::<8>::new();
+            lines.set_decorrelation(d);
+            let mut fanout = Mixer::<f32, INPUTS, 8>::new(d);
+            let mut fanin = Mixer::<f32, 8, MIXED_OUTPUTS>::new(d);
+            let mut spread = [0.0; 8];
+
+            for c in 0..INPUTS {
+                let mut channels = [0.0; INPUTS];
+                channels[c] = 1.0;
+                fanout.process(&ctx, &channels.clone(), &mut spread);
+                lines.input.process(&ctx, &spread, &mut channels);
+                assert!((channels[c] - 1.0).abs() < 1e-5, "{}: {:?}", d, channels);
+            }
+            for c in 0..MIXED_OUTPUTS {
+                let mut channels = [0.0; MIXED_OUTPUTS];
+                channels[c] = 1.0;
+                lines.output.process(&ctx, &channels, &mut spread);
+                fanin.process(&ctx, &spread, &mut channels);
+                // The square mixer of 7.1 isn't orthogonal, so its transpose only undoes it within
+                // a couple of dB
+                assert!((channels[c] - 1.0).abs() < 0.25, "{}: {:?}", d, channels);
+            }
+        }
+    }
+}
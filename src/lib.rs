@@ -1,27 +1,207 @@
 #![allow(incomplete_features)]
 #![feature(generic_associated_types)]
 
+mod algorithm;
 mod components;
 mod early_refl;
+mod freeverb;
+mod gardner;
+mod layout;
+mod mesh;
+mod modal;
+mod plate;
 mod rev_tail;
+mod room;
+mod scattering_fdn;
+mod spring;
 
 use crate::components::AudioContext;
+use algorithm::{Algorithm, Algorithms, TailSettings};
 use baseplug::{Plugin, ProcessContext};
-use components::{chorus::Hz, spread::Spread};
-use early_refl::EarlyReflections;
-use rev_tail::ReverbTail;
+use layout::OutputStage;
+use components::{
+    chorus::Hz,
+    crossover::{MonoBass, Slope},
+    drywet::DryWet,
+    ensemble::Ensemble,
+    lfo::LfoShape,
+    mixer::Mixer,
+    modulated_delay::ModShape,
+    parallel::Parallel,
+    velvet::VelvetDecorrelator,
+    width::{MidSideInput, StereoInput, Width},
+};
+use early_refl::{EarlyReflections, ErType, VelvetParams};
+use room::Room;
 use serde::{Deserialize, Serialize};
 
 baseplug::model! {
     #[derive(Debug, Serialize, Deserialize)]
     struct PluginModel {
         #[model (min = 0, max = 1.0 ,gradient="Power(0.15)")]
-        #[parameter(name = "Room size", )]
+        #[parameter(name = "ER size", )]
         size: f32,
 
+        // The tail size of every algorithm follows the ER size when enabled
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter(name = "Link sizes")]
+        size_link: f32,
+
+        // 0: hall, 1: plate, 2: room, 3: chamber, 4: spring, 5: freeverb, 6: small room,
+        // 7: medium room, 8: large room, 9: waveguide mesh, 10: modal, 11: scattering FDN
+        #[model (min = 0.0, max = 11.0)]
+        #[parameter(name = "Algorithm")]
+        algorithm: f32,
+
+        // Tail settings of each algorithm, kept apart so that every algorithm comes back as it
+        // was left when selected again
+        #[model (min = 0, max = 1.0 ,gradient="Power(0.15)")]
+        #[parameter(name = "Hall size")]
+        hall_size: f32,
+
+        #[model (min = 0.0, max = 1.0, gradient="Exponential")]
+        #[parameter(name = "Hall decay")]
+        hall_decay: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter(name = "Hall damping")]
+        hall_damping: f32,
+
+        #[model (min = 0, max = 1.0 ,gradient="Power(0.15)")]
+        #[parameter(name = "Plate size")]
+        plate_size: f32,
+
+        #[model (min = 0.0, max = 1.0, gradient="Exponential")]
+        #[parameter(name = "Plate decay")]
+        plate_decay: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter(name = "Plate damping")]
+        plate_damping: f32,
+
+        #[model (min = 0, max = 1.0 ,gradient="Power(0.15)")]
+        #[parameter(name = "Room size")]
+        room_size: f32,
+
+        #[model (min = 0.0, max = 1.0, gradient="Exponential")]
+        #[parameter(name = "Room decay")]
+        room_decay: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter(name = "Room damping")]
+        room_damping: f32,
+
+        #[model (min = 0, max = 1.0 ,gradient="Power(0.15)")]
+        #[parameter(name = "Chamber size")]
+        chamber_size: f32,
+
+        #[model (min = 0.0, max = 1.0, gradient="Exponential")]
+        #[parameter(name = "Chamber decay")]
+        chamber_decay: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter(name = "Chamber damping")]
+        chamber_damping: f32,
+
+        #[model (min = 0, max = 1.0 ,gradient="Power(0.15)")]
+        #[parameter(name = "Spring size")]
+        spring_size: f32,
+
+        #[model (min = 0.0, max = 1.0, gradient="Exponential")]
+        #[parameter(name = "Spring decay")]
+        spring_decay: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter(name = "Spring damping")]
+        spring_damping: f32,
+
+        #[model (min = 0, max = 1.0 ,gradient="Power(0.15)")]
+        #[parameter(name = "Freeverb size")]
+        freeverb_size: f32,
+
+        #[model (min = 0.0, max = 1.0, gradient="Exponential")]
+        #[parameter(name = "Freeverb decay")]
+        freeverb_decay: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter(name = "Freeverb damping")]
+        freeverb_damping: f32,
+
+        #[model (min = 0, max = 1.0 ,gradient="Power(0.15)")]
+        #[parameter(name = "Small room size")]
+        small_room_size: f32,
+
+        #[model (min = 0.0, max = 1.0, gradient="Exponential")]
+        #[parameter(name = "Small room decay")]
+        small_room_decay: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter(name = "Small room damping")]
+        small_room_damping: f32,
+
+        #[model (min = 0, max = 1.0 ,gradient="Power(0.15)")]
+        #[parameter(name = "Medium room size")]
+        medium_room_size: f32,
+
+        #[model (min = 0.0, max = 1.0, gradient="Exponential")]
+        #[parameter(name = "Medium room decay")]
+        medium_room_decay: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter(name = "Medium room damping")]
+        medium_room_damping: f32,
+
+        #[model (min = 0, max = 1.0 ,gradient="Power(0.15)")]
+        #[parameter(name = "Large room size")]
+        large_room_size: f32,
+
+        #[model (min = 0.0, max = 1.0, gradient="Exponential")]
+        #[parameter(name = "Large room decay")]
+        large_room_decay: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter(name = "Large room damping")]
+        large_room_damping: f32,
+
+        #[model (min = 0, max = 1.0 ,gradient="Power(0.15)")]
+        #[parameter(name = "Mesh size")]
+        mesh_size: f32,
+
+        #[model (min = 0.0, max = 1.0, gradient="Exponential")]
+        #[parameter(name = "Mesh decay")]
+        mesh_decay: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter(name = "Mesh damping")]
+        mesh_damping: f32,
+
+        #[model (min = 0, max = 1.0 ,gradient="Power(0.15)")]
+        #[parameter(name = "Modal size")]
+        modal_size: f32,
+
         #[model (min = 0.0, max = 1.0, gradient="Exponential")]
-        #[parameter(name = "Tail decay")]
-        feedback: f32,
+        #[parameter(name = "Modal decay")]
+        modal_decay: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter(name = "Modal damping")]
+        modal_damping: f32,
+
+        #[model (min = 0, max = 1.0 ,gradient="Power(0.15)")]
+        #[parameter(name = "Scattering size")]
+        scattering_size: f32,
+
+        #[model (min = 0.0, max = 1.0, gradient="Exponential")]
+        #[parameter(name = "Scattering decay")]
+        scattering_decay: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter(name = "Scattering damping")]
+        scattering_damping: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter(name = "Spring tension")]
+        tension: f32,
 
         #[model (min = -90.0, max = 6.0, gradient="Power(0.15)")]
         #[parameter(name = "Dry", unit="Decibels")]
@@ -35,13 +215,176 @@ baseplug::model! {
         #[parameter (name = "Wet", unit="Decibels")]
         wet_vol: f32,
 
+        // Dry/wet blend of the chorus on the tail
         #[model (min = 0.0, max = 1.0)]
-        #[parameter (name = "Modulation Amount")]
+        #[parameter (name = "Modulation Mix")]
         mod_amt: f32,
 
-        #[model (min = 0.0, max = 1.0)]
-        #[parameter (name = "Modulation Freq.")]
+        // In Hz
+        #[model (min = 0.01, max = 10.0, gradient = "Exponential")]
+        #[parameter (name = "Modulation Rate")]
         mod_freq: f32,
+
+        // In ms
+        #[model (min = 0.0, max = 50.0)]
+        #[parameter (name = "Modulation Depth")]
+        mod_depth: f32,
+
+        // 0: sine, 1: triangle, 2: saw, 3: square, 4: sample & hold, 5: smooth random
+        #[model (min = 0.0, max = 5.0)]
+        #[parameter (name = "Modulation Shape")]
+        mod_shape: f32,
+
+        // Modulation follows the host tempo instead of the rate when enabled
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "Modulation Sync")]
+        mod_sync: f32,
+
+        // Length of a modulation cycle when synced, in beats
+        #[model (min = 0.25, max = 16.0, gradient = "Exponential")]
+        #[parameter (name = "Modulation Beats")]
+        mod_beats: f32,
+
+        // Dry/wet blend of the ensemble on the wet output
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "Ensemble Mix")]
+        ensemble_mix: f32,
+
+        #[model (min = 3.0, max = 8.0)]
+        #[parameter (name = "Ensemble Voices")]
+        ensemble_voices: f32,
+
+        // In Hz
+        #[model (min = 0.05, max = 5.0, gradient = "Exponential")]
+        #[parameter (name = "Ensemble Rate")]
+        ensemble_rate: f32,
+
+        // In ms
+        #[model (min = 0.0, max = 10.0)]
+        #[parameter (name = "Ensemble Depth")]
+        ensemble_depth: f32,
+
+        #[model (min = 0.01, max = 5.0, gradient = "Exponential")]
+        #[parameter (name = "Matrix mod. rate")]
+        matrix_mod_rate: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "Matrix mod. depth")]
+        matrix_mod_depth: f32,
+
+        // 0: sine, 1: wander, 2: smoothed noise
+        #[model (min = 0.0, max = 2.0)]
+        #[parameter (name = "Tank mod. shape")]
+        tank_mod_shape: f32,
+
+        #[model (min = 0.05, max = 5.0, gradient = "Exponential")]
+        #[parameter (name = "Tank mod. rate")]
+        tank_mod_rate: f32,
+
+        // In ms
+        #[model (min = 0.0, max = 5.0)]
+        #[parameter (name = "Tank mod. depth")]
+        tank_mod_depth: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "Decorrelation")]
+        decorrelation: f32,
+
+        // 0: stereo, 1: mid only, 2: side only
+        #[model (min = 0.0, max = 2.0)]
+        #[parameter (name = "Input mode")]
+        input_mode: f32,
+
+        #[model (min = 0.0, max = 200.0)]
+        #[parameter (name = "ER width")]
+        er_width: f32,
+
+        #[model (min = 0.0, max = 200.0)]
+        #[parameter (name = "Wet width")]
+        wet_width: f32,
+
+        #[model (min = 20.0, max = 500.0, gradient = "Exponential")]
+        #[parameter (name = "Mono bass freq.")]
+        mono_bass_freq: f32,
+
+        // 0: 12 dB/oct, 1: 24 dB/oct
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "Mono bass slope")]
+        mono_bass_slope: f32,
+
+        // 0: series (tail fed by the ERs), 1: parallel (tail fed by the input)
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "ER/tail routing")]
+        routing: f32,
+
+        // Amount of ERs also sent to the tail in parallel routing
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "ER to tail send")]
+        er_send: f32,
+
+        // 0: custom room, 1: room, 2: hall, 3: chamber, 4: plate, 5: studio, 6: velvet noise
+        #[model (min = 0.0, max = 6.0)]
+        #[parameter (name = "ER type")]
+        er_type: f32,
+
+        // Impulses per second of the velvet noise ER type
+        #[model (min = 100.0, max = 4000.0, gradient = "Exponential")]
+        #[parameter (name = "Velvet density")]
+        velvet_density: f32,
+
+        // In ms
+        #[model (min = 10.0, max = 500.0, gradient = "Exponential")]
+        #[parameter (name = "Velvet length")]
+        velvet_length: f32,
+
+        #[model (min = 0.0, max = 999.0)]
+        #[parameter (name = "Velvet seed")]
+        velvet_seed: f32,
+
+        // Velvet noise decorrelation of the wet outputs
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "Output decorrelation")]
+        output_decorrelation: f32,
+
+        #[model (min = 2.0, max = 30.0, gradient = "Power(0.5)")]
+        #[parameter (name = "Room width")]
+        room_width: f32,
+
+        #[model (min = 2.0, max = 40.0, gradient = "Power(0.5)")]
+        #[parameter (name = "Room depth")]
+        room_depth: f32,
+
+        #[model (min = 2.0, max = 15.0, gradient = "Power(0.5)")]
+        #[parameter (name = "Room height")]
+        room_height: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "Source X")]
+        source_x: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "Source Y")]
+        source_y: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "Listener X")]
+        listener_x: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "Listener Y")]
+        listener_y: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "Wall absorption")]
+        wall_absorption: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "Floor absorption")]
+        floor_absorption: f32,
+
+        #[model (min = 0.0, max = 1.0)]
+        #[parameter (name = "Ceiling absorption")]
+        ceiling_absorption: f32,
     }
 }
 
@@ -49,22 +392,245 @@ impl Default for PluginModel {
     fn default() -> Self {
         Self {
             size: 0.4,
-            feedback: 0.6,
+            size_link: 1.0,
+            algorithm: 0.0,
+            hall_size: 0.4,
+            hall_decay: 0.6,
+            hall_damping: 0.0,
+            plate_size: 0.4,
+            plate_decay: 0.6,
+            plate_damping: 0.0,
+            room_size: 0.4,
+            room_decay: 0.6,
+            room_damping: 0.0,
+            chamber_size: 0.4,
+            chamber_decay: 0.6,
+            chamber_damping: 0.0,
+            spring_size: 0.4,
+            spring_decay: 0.6,
+            spring_damping: 0.0,
+            freeverb_size: 0.4,
+            freeverb_decay: 0.6,
+            freeverb_damping: 0.0,
+            small_room_size: 0.4,
+            small_room_decay: 0.6,
+            small_room_damping: 0.0,
+            medium_room_size: 0.4,
+            medium_room_decay: 0.6,
+            medium_room_damping: 0.0,
+            large_room_size: 0.4,
+            large_room_decay: 0.6,
+            large_room_damping: 0.0,
+            mesh_size: 0.4,
+            mesh_decay: 0.6,
+            mesh_damping: 0.0,
+            modal_size: 0.4,
+            modal_decay: 0.6,
+            modal_damping: 0.0,
+            scattering_size: 0.4,
+            scattering_decay: 0.6,
+            scattering_damping: 0.0,
+            tension: 0.5,
             dry_vol: 1.0,
             er_vol: 0.4,
             wet_vol: 0.5,
             mod_amt: 0.1,
             mod_freq: 0.1,
+            mod_depth: 5.0,
+            mod_shape: 0.0,
+            mod_sync: 0.0,
+            mod_beats: 4.0,
+            ensemble_mix: 0.0,
+            ensemble_voices: 4.0,
+            ensemble_rate: 0.6,
+            ensemble_depth: 3.0,
+            matrix_mod_rate: 0.3,
+            matrix_mod_depth: 0.0,
+            tank_mod_shape: 0.0,
+            tank_mod_rate: 0.5,
+            tank_mod_depth: 0.3,
+            decorrelation: 0.5,
+            input_mode: 0.0,
+            er_width: 100.0,
+            wet_width: 100.0,
+            mono_bass_freq: 120.0,
+            mono_bass_slope: 1.0,
+            routing: 0.0,
+            er_send: 0.0,
+            er_type: 0.0,
+            velvet_density: 1000.0,
+            velvet_length: 80.0,
+            velvet_seed: 0.0,
+            output_decorrelation: 0.0,
+            room_width: 8.0,
+            room_depth: 12.0,
+            room_height: 4.0,
+            source_x: 0.5,
+            source_y: 0.8,
+            listener_x: 0.5,
+            listener_y: 0.3,
+            wall_absorption: 0.3,
+            floor_absorption: 0.2,
+            ceiling_absorption: 0.4,
         }
     }
 }
 
+/// Builds the velvet noise settings of the early reflections from the model, optionally indexed
+/// at a given sample for the process-time model.
+macro_rules! model_velvet {
+    ($model:expr $(, $i:expr)?) => {
+        VelvetParams {
+            density: $model.velvet_density$([$i])?,
+            length: $model.velvet_length$([$i])? / 1000.0,
+            seed: $model.velvet_seed$([$i])?.round().max(0.0) as u32,
+        }
+    };
+}
+
+/// Builds the room of the early reflections from the model, optionally indexed at a given sample
+/// for the process-time model.
+macro_rules! model_room {
+    ($model:expr $(, $i:expr)?) => {
+        Room::from_params(
+            // The ER size scales the room like it scales the built-in patterns
+            [
+                $model.room_width$([$i])?,
+                $model.room_depth$([$i])?,
+                $model.room_height$([$i])?,
+            ]
+            .map(|d| (d * early_refl::size_scale($model.size$([$i])?)).max(1.0)),
+            [$model.source_x$([$i])?, $model.source_y$([$i])?],
+            [$model.listener_x$([$i])?, $model.listener_y$([$i])?],
+            $model.wall_absorption$([$i])?,
+            $model.floor_absorption$([$i])?,
+            $model.ceiling_absorption$([$i])?,
+        )
+    };
+}
+
+/// Builds the tail settings of every algorithm from the model, in the order of `Algorithm::ALL`,
+/// optionally indexed at a given sample for the process-time model.
+macro_rules! model_tail {
+    ($model:expr $(, $i:expr)?) => {{
+        let size = $model.size$([$i])?;
+        let link = $model.size_link$([$i])? >= 0.5;
+        let settings = |tail_size: f32, decay: f32, damping: f32| TailSettings {
+            size: if link { size } else { tail_size },
+            decay,
+            damping,
+        };
+        [
+            settings(
+                $model.hall_size$([$i])?,
+                $model.hall_decay$([$i])?,
+                $model.hall_damping$([$i])?,
+            ),
+            settings(
+                $model.plate_size$([$i])?,
+                $model.plate_decay$([$i])?,
+                $model.plate_damping$([$i])?,
+            ),
+            settings(
+                $model.room_size$([$i])?,
+                $model.room_decay$([$i])?,
+                $model.room_damping$([$i])?,
+            ),
+            settings(
+                $model.chamber_size$([$i])?,
+                $model.chamber_decay$([$i])?,
+                $model.chamber_damping$([$i])?,
+            ),
+            settings(
+                $model.spring_size$([$i])?,
+                $model.spring_decay$([$i])?,
+                $model.spring_damping$([$i])?,
+            ),
+            settings(
+                $model.freeverb_size$([$i])?,
+                $model.freeverb_decay$([$i])?,
+                $model.freeverb_damping$([$i])?,
+            ),
+            settings(
+                $model.small_room_size$([$i])?,
+                $model.small_room_decay$([$i])?,
+                $model.small_room_damping$([$i])?,
+            ),
+            settings(
+                $model.medium_room_size$([$i])?,
+                $model.medium_room_decay$([$i])?,
+                $model.medium_room_damping$([$i])?,
+            ),
+            settings(
+                $model.large_room_size$([$i])?,
+                $model.large_room_decay$([$i])?,
+                $model.large_room_damping$([$i])?,
+            ),
+            settings(
+                $model.mesh_size$([$i])?,
+                $model.mesh_decay$([$i])?,
+                $model.mesh_damping$([$i])?,
+            ),
+            settings(
+                $model.modal_size$([$i])?,
+                $model.modal_decay$([$i])?,
+                $model.modal_damping$([$i])?,
+            ),
+            settings(
+                $model.scattering_size$([$i])?,
+                $model.scattering_decay$([$i])?,
+                $model.scattering_damping$([$i])?,
+            ),
+        ]
+    }};
+}
+
+macro_rules! update_chorus {
+    ($rev_tail:expr, $model:expr $(, $i:expr)?) => {{
+        let depth = $model.mod_depth$([$i])? / 1000.0;
+        let shape = LfoShape::from_param($model.mod_shape$([$i])?);
+        let sync = $model.mod_sync$([$i])? >= 0.5;
+        let (freq, beats) = ($model.mod_freq$([$i])?, $model.mod_beats$([$i])?);
+        $rev_tail.update_chorus(move |c| {
+            c.set_depth(depth);
+            c.set_shape(shape);
+            if sync {
+                c.set_sync(beats);
+            } else {
+                c.set_frequency(Hz::from_frequency(freq));
+            }
+        });
+        $rev_tail.update_chorus_drywet($model.mod_amt$([$i])?);
+    }};
+}
+
+macro_rules! update_ensemble {
+    ($ensemble:expr, $model:expr $(, $i:expr)?) => {
+        $ensemble.set_amount($model.ensemble_mix$([$i])?);
+        $ensemble
+            .process
+            .set_voices($model.ensemble_voices$([$i])?.round() as usize);
+        $ensemble.process.set_rate($model.ensemble_rate$([$i])?);
+        $ensemble
+            .process
+            .set_depth($model.ensemble_depth$([$i])? / 1000.0);
+    };
+}
+
 struct FdnPlugin {
     audio_context: AudioContext,
     early_refl: EarlyReflections<8>,
-    rev_tail: ReverbTail<8>,
-    fanout: Spread<f32, 2, 8>,
-    fanin: Spread<f32, 8, 2>,
+    rev_tail: Algorithms<8>,
+    fanout: Mixer<f32, { layout::INPUTS }, 8>,
+    er_fanin: layout::Fanin,
+    rev_fanin: layout::Fanin,
+    dry: [[f32; layout::INPUTS]; layout::OUTPUTS],
+    input_mode: MidSideInput,
+    er_width: Width<f32>,
+    wet_width: Width<f32>,
+    mono_bass: MonoBass<f32, { layout::OUTPUTS }>,
+    decorrelators: DryWet<Parallel<VelvetDecorrelator, { layout::OUTPUTS }>, { layout::OUTPUTS }>,
+    ensemble: DryWet<Ensemble, 2>,
 }
 
 impl Plugin for FdnPlugin {
@@ -72,8 +638,8 @@ impl Plugin for FdnPlugin {
     const PRODUCT: &'static str = "Silkverb";
     const VENDOR: &'static str = "SolarLiner";
 
-    const INPUT_CHANNELS: usize = 2;
-    const OUTPUT_CHANNELS: usize = 2;
+    const INPUT_CHANNELS: usize = layout::INPUTS;
+    const OUTPUT_CHANNELS: usize = layout::OUTPUTS;
 
     type Model = PluginModel;
 
@@ -82,24 +648,56 @@ impl Plugin for FdnPlugin {
         let audio_context = AudioContext {
             sample_rate: sample_rate as _,
             sample_count: 0,
+            bpm: 120.0,
         };
         let mut early_refl = EarlyReflections::new(sample_rate);
-        let mut rev_tail = ReverbTail::new(sample_rate);
+        let mut rev_tail = Algorithms::new(sample_rate, Algorithm::from_param(model.algorithm));
+        early_refl.set_room(model_room!(model));
+        early_refl.set_velvet(model_velvet!(model));
+        early_refl.set_type(ErType::from_param(model.er_type));
         early_refl.set_delay_fract(model.size);
-        rev_tail.update_size(model.size);
-        rev_tail.update_feedback(model.feedback);
-        rev_tail.update_chorus(|c| {
-            c.set_amplitude(0.1);
-            c.set_frequency(Hz::from_frequency(model.mod_freq));
-        });
-        rev_tail.update_chorus_drywet(model.mod_amt);
+        rev_tail.update_settings(&model_tail!(model));
+        rev_tail.update_tension(model.tension);
+        rev_tail.update_decorrelation(model.decorrelation);
+        update_chorus!(rev_tail, model);
+        rev_tail.update_matrix_modulation(model.matrix_mod_rate, model.matrix_mod_depth);
+        rev_tail.update_tank_modulation(
+            ModShape::from_param(model.tank_mod_shape),
+            model.tank_mod_rate,
+            model.tank_mod_depth / 1000.0,
+        );
+        rev_tail.update_block();
 
         Self {
             audio_context,
             early_refl,
             rev_tail,
-            fanout: Spread::default(),
-            fanin: Spread::default(),
+            fanout: Mixer::new(model.decorrelation),
+            // The output stages can hold state, so ER and tail each get their own
+            er_fanin: OutputStage::new(sample_rate, model.decorrelation),
+            rev_fanin: OutputStage::new(sample_rate, model.decorrelation),
+            dry: layout::dry_matrix(),
+            input_mode: MidSideInput::new(StereoInput::from_param(model.input_mode)),
+            er_width: Width::new(model.er_width / 100.0),
+            wet_width: Width::new(model.wet_width / 100.0),
+            mono_bass: MonoBass::new(
+                sample_rate,
+                model.mono_bass_freq,
+                Slope::from_param(model.mono_bass_slope),
+                layout::LFE,
+            ),
+            decorrelators: {
+                let mut decorrelators = DryWet::new(Parallel::new(|i| {
+                    VelvetDecorrelator::new(sample_rate, i as u32 + 1)
+                }));
+                decorrelators.set_amount(model.output_decorrelation);
+                decorrelators
+            },
+            ensemble: {
+                let mut ensemble = DryWet::new(Ensemble::new(sample_rate));
+                update_ensemble!(ensemble, model);
+                ensemble
+            },
         }
     }
 
@@ -107,46 +705,129 @@ impl Plugin for FdnPlugin {
     fn process<'proc>(&mut self, model: &PluginModelProcess, ctx: &'proc mut ProcessContext<Self>) {
         use components::Process;
 
+        self.audio_context.bpm = ctx.musical_time.bpm as f32;
+
+        // Computing the reflections and restructuring the tail are too costly to be done every
+        // sample; the tail picks up the parameters of the previous block
+        if ctx.nframes > 0 {
+            self.early_refl.set_room(model_room!(model, 0));
+            self.early_refl.set_velvet(model_velvet!(model, 0));
+            self.rev_tail.update_block();
+        }
+
         for i in 0..ctx.nframes {
-            let inputs = [ctx.inputs[0].buffers[0][i], ctx.inputs[0].buffers[1][i]];
+            let mut inputs = [0.0; layout::INPUTS];
+            for (c, x) in inputs.iter_mut().enumerate() {
+                *x = ctx.inputs[0].buffers[c][i];
+            }
+            let mut rev_source = inputs;
             let mut rev_input = [0.0; 8];
             let mut er_output_in = [0.0; 8];
+            let mut rev_tail_in = [0.0; 8];
             let mut rev_output_in = [0.0; 8];
-            let mut er_out = [0.0; 2];
-            let mut rev_out = [0.0; 2];
+            let mut er_out = [0.0; layout::OUTPUTS];
+            let mut rev_out = [0.0; layout::OUTPUTS];
+            let mut wet_mix = [0.0; layout::OUTPUTS];
+            let mut wet_out = [0.0; layout::OUTPUTS];
 
+            self.early_refl.set_type(ErType::from_param(model.er_type[i]));
             self.early_refl.set_delay_fract(model.size[i]);
-            self.rev_tail.update_size(model.size[i]);
-            self.rev_tail.update_feedback(model.feedback[i]);
-            self.rev_tail.update_chorus({
-                let f = Hz::from_frequency(model.mod_freq[i]);
-                move |c| {
-                    c.set_frequency(f);
-                }
-            });
-            self.rev_tail.update_chorus_drywet(model.mod_amt[i]);
+            self.rev_tail
+                .set_algorithm(Algorithm::from_param(model.algorithm[i]));
+            self.rev_tail.update_settings(&model_tail!(model, i));
+            self.rev_tail.update_tension(model.tension[i]);
+            update_chorus!(self.rev_tail, model, i);
+            self.rev_tail
+                .update_matrix_modulation(model.matrix_mod_rate[i], model.matrix_mod_depth[i]);
+            self.rev_tail.update_tank_modulation(
+                ModShape::from_param(model.tank_mod_shape[i]),
+                model.tank_mod_rate[i],
+                model.tank_mod_depth[i] / 1000.0,
+            );
+            self.fanout.set_decorrelation(model.decorrelation[i]);
+            self.rev_tail.update_decorrelation(model.decorrelation[i]);
+            OutputStage::set_decorrelation(&mut self.er_fanin, model.decorrelation[i]);
+            OutputStage::set_decorrelation(&mut self.rev_fanin, model.decorrelation[i]);
+            self.input_mode.mode = StereoInput::from_param(model.input_mode[i]);
+            self.er_width.width = model.er_width[i] / 100.0;
+            self.wet_width.width = model.wet_width[i] / 100.0;
+            self.decorrelators
+                .set_amount(model.output_decorrelation[i]);
+            update_ensemble!(self.ensemble, model, i);
+            self.mono_bass.set(
+                model.mono_bass_freq[i],
+                Slope::from_param(model.mono_bass_slope[i]),
+            );
 
+            if layout::INPUTS >= 2 {
+                process_front_pair(&mut self.input_mode, &self.audio_context, &mut rev_source);
+            }
+            // The LFE channel only carries dry low end, it isn't sent to the reverb
+            if let Some(lfe) = layout::LFE {
+                rev_source[lfe] = 0.0;
+            }
             self.fanout
-                .process(&self.audio_context, &inputs, &mut rev_input);
+                .process(&self.audio_context, &rev_source, &mut rev_input);
             self.early_refl
                 .process(&self.audio_context, &rev_input, &mut er_output_in);
+            let parallel = model.routing[i];
+            for (k, x) in rev_tail_in.iter_mut().enumerate() {
+                let parallel_in = rev_input[k] + model.er_send[i] * er_output_in[k];
+                *x = parallel * parallel_in + (1.0 - parallel) * er_output_in[k];
+            }
             self.rev_tail
-                .process(&self.audio_context, &er_output_in, &mut rev_output_in);
-            self.fanin
+                .process(&self.audio_context, &rev_tail_in, &mut rev_output_in);
+            self.er_fanin
                 .process(&self.audio_context, &er_output_in, &mut er_out);
-            self.fanin
+            self.rev_fanin
                 .process(&self.audio_context, &rev_output_in, &mut rev_out);
+            if !layout::AMBISONICS && !layout::BINAURAL {
+                process_front_pair(&mut self.er_width, &self.audio_context, &mut er_out);
+                process_front_pair(&mut self.wet_width, &self.audio_context, &mut rev_out);
+            }
 
-            ctx.outputs[0].buffers[0][i] = rev_out[0] * model.wet_vol[i]
-                + er_out[0] * model.er_vol[i]
-                + inputs[0] * model.dry_vol[i];
-            ctx.outputs[0].buffers[1][i] = rev_out[1] * model.wet_vol[i]
-                + er_out[1] * model.er_vol[i]
-                + inputs[1] * model.dry_vol[i];
+            for c in 0..layout::OUTPUTS {
+                wet_mix[c] = rev_out[c] * model.wet_vol[i] + er_out[c] * model.er_vol[i];
+            }
+            if layout::AMBISONICS {
+                wet_out = wet_mix;
+            } else {
+                let mut decorrelated = wet_mix;
+                // The ensemble and the decorrelators would smear the interaural time differences
+                // of the binaural rendering
+                if !layout::BINAURAL {
+                    process_front_pair(&mut self.ensemble, &self.audio_context, &mut wet_mix);
+                    self.decorrelators
+                        .process(&self.audio_context, &wet_mix, &mut decorrelated);
+                }
+                self.mono_bass
+                    .process(&self.audio_context, &decorrelated, &mut wet_out);
+            }
+            if let Some(lfe) = layout::LFE {
+                wet_out[lfe] = 0.0;
+            }
+
+            for (c, wet) in wet_out.iter().enumerate() {
+                let dry = self.dry[c]
+                    .iter()
+                    .zip(inputs.iter())
+                    .fold(0.0, |acc, (g, x)| acc + g * x);
+                ctx.outputs[0].buffers[c][i] = wet + dry * model.dry_vol[i];
+            }
             self.audio_context.sample_count += 1;
         }
     }
 }
 
+/// Runs a stereo process in place on the front left/right pair of a frame.
+fn process_front_pair<P: components::Process<T = f32>>(
+    process: &mut P,
+    ctx: &AudioContext,
+    frame: &mut [f32],
+) {
+    let pair = [frame[0], frame[1]];
+    process.process(ctx, &pair, &mut frame[0..2]);
+}
+
 #[cfg(not(test))]
 baseplug::vst2!(FdnPlugin, b"S1lK");
@@ -0,0 +1,192 @@
+use crate::components::{filter::OnePole, AudioContext, Process, SingleChannelProcess};
+use crate::layout::StereoLines;
+
+/// Largest mesh, in nodes along the width; the height follows with `ASPECT`
+const MAX_WIDTH: usize = 56;
+/// Smallest mesh, in nodes along the width
+const MIN_WIDTH: usize = 12;
+/// Height over width ratio of the mesh
+const ASPECT: f32 = 0.75;
+/// Input positions of the left and right channels, relative to the mesh dimensions
+const INPUTS: [[f32; 2]; 2] = [[0.3, 0.45], [0.7, 0.4]];
+/// Highest loss at each boundary reflection, reached at zero decay
+const MAX_LOSS: f32 = 0.05;
+/// Gain of the pickups, bringing the mesh in line with the other algorithms
+const OUTPUT_GAIN: f32 = 0.1;
+
+fn height(width: usize) -> usize {
+    ((width as f32 * ASPECT).round() as usize).max(3)
+}
+
+/// Two-dimensional rectilinear digital waveguide mesh, in its equivalent finite-difference form:
+/// each node is updated from the mean of its four neighbours and its own past value. The mesh
+/// edges are one-dimensional lossy boundaries whose reflection coefficient sets the decay, the
+/// reflected wave going through a lowpass for damping.
+///
+/// The mesh is excited at two points from the stereo input of `StereoLines`, and N pickups write
+/// back to the lines, even lines on the left half of the mesh and odd lines on the right half.
+pub(crate) struct WaveguideMesh<const N: usize> {
+    sample_rate: f32,
+    lines: StereoLines<N>,
+    /// Node values at the previous, current and next time steps, with rows `MAX_WIDTH` long
+    prev: Vec<f32>,
+    cur: Vec<f32>,
+    next: Vec<f32>,
+    /// Damping filters of the boundary nodes
+    boundary: Vec<OnePole<f32>>,
+    /// Last two stereo inputs, for the DC-free excitation
+    history: [[f32; 2]; 2],
+    width: usize,
+    height: usize,
+    /// Width set by `update_size`, applied by `update_block`
+    target_width: usize,
+    reflection: f32,
+    cutoff: f32,
+}
+
+impl<const N: usize> WaveguideMesh<N> {
+    pub fn new(sample_rate: f32) -> Self {
+        let nodes = MAX_WIDTH * height(MAX_WIDTH);
+        Self {
+            sample_rate,
+            lines: StereoLines::new(),
+            prev: vec![0.0; nodes],
+            cur: vec![0.0; nodes],
+            next: vec![0.0; nodes],
+            boundary: (0..nodes).map(|_| OnePole::bypass()).collect(),
+            history: [[0.0; 2]; 2],
+            width: MIN_WIDTH,
+            height: height(MIN_WIDTH),
+            target_width: MIN_WIDTH,
+            reflection: 0.99,
+            cutoff: f32::INFINITY,
+        }
+    }
+
+    /// Sets the size of the mesh, which takes effect at the next `update_block`.
+    pub fn update_size(&mut self, size: f32) {
+        let size = size.max(0.0).min(1.0);
+        self.target_width = MIN_WIDTH + ((MAX_WIDTH - MIN_WIDTH) as f32 * size).round() as usize;
+    }
+
+    /// Resizes the active part of the mesh, clearing the nodes outside of it. Resizing moves the
+    /// boundaries at once, so it is only done once per block.
+    pub fn update_block(&mut self) {
+        if self.target_width == self.width {
+            return;
+        }
+        self.width = self.target_width;
+        self.height = height(self.width);
+        for y in 0..height(MAX_WIDTH) {
+            for x in 0..MAX_WIDTH {
+                if x >= self.width || y >= self.height {
+                    let i = y * MAX_WIDTH + x;
+                    self.prev[i] = 0.0;
+                    self.cur[i] = 0.0;
+                    self.next[i] = 0.0;
+                    self.boundary[i] = OnePole::new(self.sample_rate, self.cutoff);
+                }
+            }
+        }
+    }
+
+    /// Sets the reflection coefficient of the boundaries. The mesh is small, so waves hit the
+    /// boundaries hundreds of times per second, and the loss per reflection has to stay small.
+    pub fn update_feedback(&mut self, feedback: f32) {
+        let loss = 1.0 - feedback.max(0.0).min(1.0);
+        self.reflection = 1.0 - MAX_LOSS * loss * loss;
+    }
+
+    pub fn update_damping(&mut self, cutoff: f32) {
+        self.cutoff = cutoff;
+        for filter in &mut self.boundary {
+            filter.set_cutoff(self.sample_rate, cutoff);
+        }
+    }
+
+    /// Follows the decorrelation of the plugin mixers, see `StereoLines`.
+    pub fn update_decorrelation(&mut self, decorrelation: f32) {
+        self.lines.set_decorrelation(decorrelation);
+    }
+
+    fn pickup(&self, line: usize) -> f32 {
+        let rows = (N + 1) / 2;
+        let x = if line % 2 == 0 { 0.2 } else { 0.8 } + 0.03 * (line / 2) as f32;
+        let y = ((line / 2) as f32 + 0.5) / rows as f32;
+        let x = (x * (self.width - 1) as f32).round() as usize;
+        let y = (y * (self.height - 1) as f32).round() as usize;
+        OUTPUT_GAIN * self.cur[y * MAX_WIDTH + x.min(self.width - 1)]
+    }
+}
+
+impl<const N: usize> Process for WaveguideMesh<N> {
+    type T = f32;
+    const NIN: usize = N;
+    const NOUT: usize = N;
+
+    fn process(&mut self, ctx: &AudioContext, input_frame: &[f32], output_frame: &mut [f32]) {
+        let stereo = self.lines.downmix(ctx, input_frame);
+        let (w, h) = (self.width, self.height);
+        let r = self.reflection;
+
+        for y in 0..h {
+            for x in 0..w {
+                let i = y * MAX_WIDTH + x;
+                let edge_x = x == 0 || x == w - 1;
+                let edge_y = y == 0 || y == h - 1;
+                self.next[i] = if edge_x || edge_y {
+                    // One-dimensional boundary driven by the inward neighbours
+                    let mut inward = 0.0;
+                    let mut count = 0.0;
+                    if edge_x {
+                        inward += self.cur[if x == 0 { i + 1 } else { i - 1 }];
+                        count += 1.0;
+                    }
+                    if edge_y {
+                        inward += self.cur[if y == 0 { i + MAX_WIDTH } else { i - MAX_WIDTH }];
+                        count += 1.0;
+                    }
+                    // Only the reflected wave goes through the damping, which keeps the
+                    // boundary passive
+                    let inward = inward / count;
+                    let reflected = inward - self.prev[i];
+                    inward + r * self.boundary[i].process_single_channel(ctx, reflected)
+                } else {
+                    0.5 * (self.cur[i - 1]
+                        + self.cur[i + 1]
+                        + self.cur[i - MAX_WIDTH]
+                        + self.cur[i + MAX_WIDTH])
+                        - self.prev[i]
+                };
+            }
+        }
+        // The mesh has a constant and a linearly growing mode, which the input can't excite once
+        // differentiated over two samples
+        let excitation = [
+            stereo[0] - self.history[1][0],
+            stereo[1] - self.history[1][1],
+        ];
+        self.history = [stereo, self.history[0]];
+        for (pos, x) in INPUTS.iter().zip(excitation.iter()) {
+            let px = 1 + (pos[0] * (w - 2) as f32) as usize;
+            let py = 1 + (pos[1] * (h - 2) as f32) as usize;
+            self.next[py * MAX_WIDTH + px] += x;
+        }
+
+        std::mem::swap(&mut self.prev, &mut self.cur);
+        std::mem::swap(&mut self.cur, &mut self.next);
+        for (line, out) in output_frame.iter_mut().enumerate() {
+            *out = self.pickup(line);
+        }
+    }
+
+    fn reset(&mut self) {
+        for nodes in [&mut self.prev, &mut self.cur, &mut self.next] {
+            nodes.iter_mut().for_each(|x| *x = 0.0);
+        }
+        self.boundary
+            .iter_mut()
+            .for_each(|b| b.reset_single_channel());
+        self.history = [[0.0; 2]; 2];
+    }
+}
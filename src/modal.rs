@@ -0,0 +1,209 @@
+use std::f32::consts::TAU;
+
+use crate::components::{rng::Rng, AudioContext, Process, SPEED_OF_SOUND};
+use crate::layout::StereoLines;
+
+/// Number of resonators, shared between the lines
+const MODES: usize = 512;
+/// Highest generated mode frequency, in Hz
+const MAX_FREQUENCY: f32 = 12000.0;
+/// Room lengths at the smallest and largest sizes, in meters, setting the lowest mode
+const LENGTH: [f32; 2] = [3.0, 40.0];
+/// Decay times at zero and full decay, in seconds
+const T60: [f32; 2] = [0.1, 10.0];
+/// Seed of the mode frequencies and gains, so that the reverb is the same on every instance
+const SEED: u32 = 0x5EED;
+/// Gain of the generated modes, bringing the bank in line with the other algorithms
+const OUTPUT_GAIN: f32 = 0.03;
+
+/// A single decaying resonance.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Mode {
+    /// Frequency, in Hz
+    frequency: f32,
+    /// Time to decay by 60 dB, in seconds
+    t60: f32,
+    /// Amplitude of the impulse response of the mode
+    gain: f32,
+}
+
+/// Resonators of one line, stored as a structure of arrays so that updates vectorize.
+struct Bank {
+    re: Vec<f32>,
+    im: Vec<f32>,
+    pole_re: Vec<f32>,
+    pole_im: Vec<f32>,
+    gain: Vec<f32>,
+}
+
+impl Bank {
+    fn new(len: usize) -> Self {
+        Self {
+            re: vec![0.0; len],
+            im: vec![0.0; len],
+            pole_re: vec![0.0; len],
+            pole_im: vec![0.0; len],
+            gain: vec![0.0; len],
+        }
+    }
+
+    fn set(&mut self, k: usize, mode: &Mode, sample_rate: f32) {
+        let radius = (-6.9078 / (mode.t60.max(1e-3) * sample_rate)).exp();
+        let omega = TAU * mode.frequency / sample_rate;
+        self.pole_re[k] = radius * omega.cos();
+        self.pole_im[k] = radius * omega.sin();
+        self.gain[k] = mode.gain;
+    }
+
+    #[inline(always)]
+    fn process(&mut self, x: f32) -> f32 {
+        let len = self.re.len();
+        let (re, im) = (&mut self.re[..len], &mut self.im[..len]);
+        let (pr, pi, g) = (
+            &self.pole_re[..len],
+            &self.pole_im[..len],
+            &self.gain[..len],
+        );
+        let mut out = 0.0;
+        for k in 0..len {
+            let (zr, zi) = (re[k], im[k]);
+            re[k] = zr * pr[k] - zi * pi[k] + g[k] * x;
+            im[k] = zr * pi[k] + zi * pr[k];
+            out += im[k];
+        }
+        out
+    }
+}
+
+/// Modal reverb: the room is a bank of complex one-pole resonators, each a decaying sine at one of
+/// the room modes, generated from the size, decay and damping.
+///
+/// The stereo input of `StereoLines` is summed to mono, and the modes are dealt over the lines for
+/// the output.
+pub(crate) struct ModalReverb<const N: usize> {
+    sample_rate: f32,
+    lines: StereoLines<N>,
+    banks: Vec<Bank>,
+    size: f32,
+    feedback: f32,
+    cutoff: f32,
+    /// Whether the parameters changed since the modes were last generated
+    dirty: bool,
+}
+
+impl<const N: usize> ModalReverb<N> {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut modal = Self {
+            sample_rate,
+            lines: StereoLines::new(),
+            // Modes are dealt over the lines so that each line gets the whole spectrum
+            banks: (0..N)
+                .map(|line| Bank::new((MODES + N - 1 - line) / N))
+                .collect(),
+            size: 0.5,
+            feedback: 0.5,
+            cutoff: f32::INFINITY,
+            dirty: true,
+        };
+        modal.update_block();
+        modal
+    }
+
+    pub fn update_size(&mut self, size: f32) {
+        if size != self.size {
+            self.size = size;
+            self.dirty = true;
+        }
+    }
+
+    pub fn update_feedback(&mut self, feedback: f32) {
+        if feedback != self.feedback {
+            self.feedback = feedback;
+            self.dirty = true;
+        }
+    }
+
+    pub fn update_damping(&mut self, cutoff: f32) {
+        if cutoff != self.cutoff {
+            self.cutoff = cutoff;
+            self.dirty = true;
+        }
+    }
+
+    /// Follows the decorrelation of the plugin mixers, see `StereoLines`.
+    pub fn update_decorrelation(&mut self, decorrelation: f32) {
+        self.lines.set_decorrelation(decorrelation);
+    }
+
+    /// Generates the modes again if the parameters changed. Every mode is recomputed, so this is
+    /// only done once per block.
+    pub fn update_block(&mut self) {
+        if self.dirty {
+            self.dirty = false;
+            self.generate();
+        }
+    }
+
+    /// Sets the modes of a room following the parameters: the lowest mode is set by the size, mode
+    /// density grows with frequency, and high frequencies decay faster with damping.
+    fn generate(&mut self) {
+        let length = LENGTH[0] + (LENGTH[1] - LENGTH[0]) * self.size.max(0.0).min(1.0);
+        let low = (SPEED_OF_SOUND / (2.0 * length)).max(20.0);
+        let high = MAX_FREQUENCY.min(0.45 * self.sample_rate);
+        let feedback = self.feedback.max(0.0).min(1.0);
+        let t60 = T60[0] + (T60[1] - T60[0]) * feedback * feedback;
+        let gain = OUTPUT_GAIN / (MODES as f32).sqrt();
+
+        let mut rng = Rng::new(SEED);
+        for k in 0..MODES {
+            // Stratified so that modes don't clump, with a density proportional to frequency
+            let u = (k as f32 + rng.next_f32()) / MODES as f32;
+            let frequency = (low * low + u * (high * high - low * low)).sqrt();
+            let damping = frequency / self.cutoff;
+            let mode = Mode {
+                frequency,
+                t60: t60 / (1.0 + damping * damping),
+                gain: gain * rng.next_bipolar().signum(),
+            };
+            self.banks[k % N].set(k / N, &mode, self.sample_rate);
+        }
+    }
+}
+
+impl<const N: usize> Process for ModalReverb<N> {
+    type T = f32;
+    const NIN: usize = N;
+    const NOUT: usize = N;
+
+    fn process(&mut self, ctx: &AudioContext, input_frame: &[f32], output_frame: &mut [f32]) {
+        let stereo = self.lines.downmix(ctx, input_frame);
+        let x = 0.5 * (stereo[0] + stereo[1]);
+        for (bank, out) in self.banks.iter_mut().zip(output_frame.iter_mut()) {
+            *out = bank.process(x);
+        }
+    }
+
+    fn reset(&mut self) {
+        for bank in &mut self.banks {
+            bank.re.iter_mut().for_each(|x| *x = 0.0);
+            bank.im.iter_mut().for_each(|x| *x = 0.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modes_follow_parameters_on_block() {
+        let mut modal = ModalReverb::<8>::new(48000.0);
+        let poles = modal.banks[0].pole_re.clone();
+        modal.update_size(1.0);
+        modal.update_feedback(0.9);
+        assert_eq!(modal.banks[0].pole_re, poles);
+        modal.update_block();
+        assert_ne!(modal.banks[0].pole_re, poles);
+        assert_eq!(modal.banks[0].pole_re.len(), MODES / 8);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::components::{
+    delay::DelayLine,
+    filter::OnePole,
+    lfo::{Lfo, LfoRate, LfoShape},
+    schroeder::SchroederAllpass,
+    AudioContext, Process, SingleChannelProcess,
+};
+use crate::layout::StereoLines;
+
+/// Sample rate the delays of the original figure-of-eight tank are given at
+const REFERENCE_RATE: f32 = 29761.0;
+/// Input diffusers as (delay in samples at the reference rate, gain)
+const INPUT_DIFFUSION: [(f32, f32); 4] =
+    [(142.0, 0.75), (107.0, 0.75), (379.0, 0.625), (277.0, 0.625)];
+/// Delays of each tank half at the reference rate: modulated allpass, first delay, allpass and
+/// second delay
+const TANK: [[f32; 4]; 2] = [
+    [672.0, 4453.0, 1800.0, 3720.0],
+    [908.0, 4217.0, 2656.0, 3163.0],
+];
+/// Gains of the modulated allpasses and of the allpasses after the damping
+const DECAY_DIFFUSION: [f32; 2] = [-0.7, 0.5];
+/// Peak modulation of the tank allpasses, in samples at the reference rate
+const EXCURSION: f32 = 16.0;
+/// Rate of the tank allpass modulation, in Hz
+const MOD_RATE: f32 = 1.0;
+/// Scale applied to the tank delays at the largest size
+const MAX_SCALE: f32 = 2.0;
+/// Cutoff of the input bandwidth filter, in Hz
+const BANDWIDTH: f32 = 12000.0;
+/// Output taps as (tank half, node, delay at the reference rate, sign), nodes being the first
+/// delay, the allpass and the second delay of the half
+type Taps = [(usize, usize, f32, f32); 7];
+const LEFT_TAPS: Taps = [
+    (1, 0, 266.0, 1.0),
+    (1, 0, 2974.0, 1.0),
+    (1, 1, 1913.0, -1.0),
+    (1, 2, 1996.0, 1.0),
+    (0, 0, 1990.0, -1.0),
+    (0, 1, 187.0, -1.0),
+    (0, 2, 1066.0, -1.0),
+];
+const RIGHT_TAPS: Taps = [
+    (0, 0, 353.0, 1.0),
+    (0, 0, 3627.0, 1.0),
+    (0, 1, 1228.0, -1.0),
+    (0, 2, 2673.0, 1.0),
+    (1, 0, 2111.0, -1.0),
+    (1, 1, 335.0, -1.0),
+    (1, 2, 121.0, -1.0),
+];
+
+struct TankHalf {
+    modulated: SchroederAllpass<f32>,
+    lfo: Lfo,
+    first: DelayLine<f32>,
+    damping: OnePole<f32>,
+    diffuser: SchroederAllpass<f32>,
+    second: DelayLine<f32>,
+    /// Current delays, in samples, in the same order as `TANK`
+    delays: [f32; 4],
+}
+
+impl TankHalf {
+    fn new(sample_rate: f32, delays: [f32; 4], phase: f32) -> Self {
+        let rate = sample_rate / REFERENCE_RATE;
+        let len = |d: f32| (d * rate * MAX_SCALE + EXCURSION * rate).ceil() as usize + 2;
+        Self {
+            modulated: SchroederAllpass::new(len(delays[0]), delays[0] * rate, DECAY_DIFFUSION[0]),
+            lfo: Lfo::new(LfoShape::Sine, LfoRate::Hz(MOD_RATE), phase, 0),
+            first: DelayLine::new(len(delays[1])),
+            damping: OnePole::bypass(),
+            diffuser: SchroederAllpass::new(len(delays[2]), delays[2] * rate, DECAY_DIFFUSION[1]),
+            second: DelayLine::new(len(delays[3])),
+            delays: delays.map(|d| d * rate),
+        }
+    }
+
+    fn tap(&self, node: usize, delay: f32) -> f32 {
+        match node {
+            0 => self.first.read(delay),
+            1 => self.diffuser.tap(delay),
+            _ => self.second.read(delay),
+        }
+    }
+}
+
+/// Plate reverb after Dattorro's "Effect Design, Part 1": a mono input goes through a bandwidth
+/// filter and a chain of diffusers into a figure-of-eight tank of two halves feeding each other,
+/// each made of a modulated allpass, a damped delay and a second allpass. The stereo output is
+/// built from taps spread over the tank.
+///
+/// The lines come in and go out through `StereoLines`, the input being summed to mono.
+pub(crate) struct Plate<const N: usize> {
+    sample_rate: f32,
+    lines: StereoLines<N>,
+    bandwidth: OnePole<f32>,
+    diffusion: Vec<SchroederAllpass<f32>>,
+    tank: [TankHalf; 2],
+    feedback: [f32; 2],
+    decay: f32,
+    scale: f32,
+}
+
+impl<const N: usize> Plate<N> {
+    pub fn new(sample_rate: f32) -> Self {
+        let rate = sample_rate / REFERENCE_RATE;
+        Self {
+            sample_rate,
+            lines: StereoLines::new(),
+            bandwidth: OnePole::new(sample_rate, BANDWIDTH),
+            diffusion: INPUT_DIFFUSION
+                .iter()
+                .map(|&(d, g)| SchroederAllpass::new((d * rate).ceil() as usize + 1, d * rate, g))
+                .collect(),
+            // The halves are modulated in quadrature
+            tank: [
+                TankHalf::new(sample_rate, TANK[0], 0.0),
+                TankHalf::new(sample_rate, TANK[1], 0.25),
+            ],
+            feedback: [0.0; 2],
+            decay: 0.5,
+            scale: rate,
+        }
+    }
+
+    /// Scales the tank, a size of 0.5 giving the original delays.
+    pub fn update_size(&mut self, size: f32) {
+        let size = (2.0 * size).max(0.1).min(MAX_SCALE);
+        let scale = size * self.sample_rate / REFERENCE_RATE;
+        self.scale = scale;
+        for (half, delays) in self.tank.iter_mut().zip(TANK.iter()) {
+            half.delays = delays.map(|d| d * scale);
+            half.diffuser.set_delay(half.delays[2]);
+        }
+    }
+
+    pub fn update_feedback(&mut self, feedback: f32) {
+        self.decay = feedback.max(0.0).min(1.0);
+    }
+
+    pub fn update_damping(&mut self, cutoff: f32) {
+        for half in &mut self.tank {
+            half.damping.set_cutoff(self.sample_rate, cutoff);
+        }
+    }
+
+    /// Follows the decorrelation of the plugin mixers, see `StereoLines`.
+    pub fn update_decorrelation(&mut self, decorrelation: f32) {
+        self.lines.set_decorrelation(decorrelation);
+    }
+
+    fn taps(&self, taps: &Taps) -> f32 {
+        0.6 * taps.iter().fold(0.0, |acc, &(half, node, delay, sign)| {
+            acc + sign * self.tank[half].tap(node, delay * self.scale)
+        })
+    }
+}
+
+impl<const N: usize> Process for Plate<N> {
+    type T = f32;
+    const NIN: usize = N;
+    const NOUT: usize = N;
+
+    fn process(&mut self, ctx: &AudioContext, input_frame: &[f32], output_frame: &mut [f32]) {
+        let stereo = self.lines.downmix(ctx, input_frame);
+        let mut x = self
+            .bandwidth
+            .process_single_channel(ctx, 0.5 * (stereo[0] + stereo[1]));
+        for d in &mut self.diffusion {
+            x = d.process_single_channel(ctx, x);
+        }
+
+        let excursion = EXCURSION * self.sample_rate / REFERENCE_RATE;
+        let feedback = self.feedback;
+        for (i, half) in self.tank.iter_mut().enumerate() {
+            // Each half is fed by the end of the other one
+            let input = x + self.decay * feedback[1 - i];
+            let lfo = half.lfo.next(ctx);
+            half.modulated.set_delay(half.delays[0] + excursion * lfo);
+            let a = half.modulated.process_single_channel(ctx, input);
+            half.first.push_pop(a);
+            let b = half.first.read(half.delays[1]);
+            let b = self.decay * half.damping.process_single_channel(ctx, b);
+            let c = half.diffuser.process_single_channel(ctx, b);
+            half.second.push_pop(c);
+            self.feedback[i] = half.second.read(half.delays[3]);
+        }
+
+        let stereo = [self.taps(&LEFT_TAPS), self.taps(&RIGHT_TAPS)];
+        self.lines.upmix(ctx, &stereo, output_frame);
+    }
+
+    fn reset(&mut self) {
+        self.bandwidth.reset_single_channel();
+        self.diffusion
+            .iter_mut()
+            .for_each(|d| d.reset_single_channel());
+        for half in &mut self.tank {
+            half.modulated.reset_single_channel();
+            half.first.clear();
+            half.damping.reset_single_channel();
+            half.diffuser.reset_single_channel();
+            half.second.clear();
+        }
+        self.feedback = [0.0; 2];
+    }
+}
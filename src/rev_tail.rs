@@ -1,41 +1,65 @@
-use audio::Sample;
-use nalgebra::ClosedMul;
+use crate::components::{Process, allpass::Allpass, chorus::Chorus, drywet::DryWet, feedback::Feedback, filter::DampedGain, modulated_delay::{ModShape, ModulatedDelay}, parallel::Parallel, rotation::ModulatedRotation, seq::Sequence};
 
-use crate::components::{Process, SingleChannelProcess, allpass::Allpass, chorus::Chorus, drywet::DryWet, feedback::Feedback, parallel::Parallel, seq::Sequence};
-
-struct Gain<T> {
-    gain: T,
+/// Delay tunings of a `ReverbTail`, in seconds.
+pub(crate) struct TailTuning {
+    /// Longest delays of the two diffusion steps inside the feedback loop
+    pub tank: [f32; 2],
+    /// Longest delays of the diffusion steps applied before the feedback loop
+    pub input_diffusion: &'static [f32],
 }
 
-impl<T: Sample + ClosedMul> SingleChannelProcess for Gain<T> {
-    type T = T;
+/// Longest modulation depth of the delays inside the feedback loop, in seconds
+pub(crate) const MAX_TANK_MOD_DEPTH: f32 = 0.005;
 
-    fn process_single_channel(
-        &mut self,
-        _: &crate::components::AudioContext,
-        value: Self::T,
-    ) -> Self::T {
-        value * self.gain
-    }
-}
+/// Longest swing of the output chorus, in seconds
+pub(crate) const MAX_CHORUS_DEPTH: f32 = 0.05;
+
+/// Backward path of the tank: modulated delays, then the damped feedback gains
+type TankReturn<const N: usize> =
+    Sequence<f32, Parallel<ModulatedDelay, N>, Parallel<DampedGain<f32>, N>>;
 
-pub(crate) struct ReverbTail<const N: usize> {
-    tank: Feedback<Sequence<f32, Allpass<N>, Allpass<N>>, Parallel<Gain<f32>, N>, N>,
+/// Feedback reverb tail, with the mixing matrix `M` of the allpasses inside the feedback loop.
+pub(crate) struct ReverbTail<const N: usize, M: Process<T = f32>> {
+    diffusion: Vec<Allpass<N>>,
+    tank: Feedback<Sequence<f32, Allpass<N, M>, Allpass<N, M>>, TankReturn<N>, N>,
     modulation: DryWet<Parallel<Chorus<f32>, N>, N>,
 }
 
-impl<const N: usize> ReverbTail<N> {
-    pub fn new(samplerate: f32) -> Self {
+impl<const N: usize> ReverbTail<N, ModulatedRotation<N>> {
+    /// Sets the rate (in Hz) and depth of the rotation of the feedback matrices.
+    pub fn update_matrix_modulation(&mut self, rate: f32, depth: f32) {
+        let seq = self.tank.forward_mut();
+        for mixer in [seq.pa.mixer_mut(), seq.pb.mixer_mut()] {
+            mixer.set_rate(rate);
+            mixer.depth = depth;
+        }
+    }
+}
+
+impl<const N: usize, M: Process<T = f32>> ReverbTail<N, M> {
+    /// Creates the tail, `mixer` creating the mixing matrix of each allpass of the feedback loop.
+    pub fn new(samplerate: f32, tuning: &TailTuning, mut mixer: impl FnMut() -> M) -> Self {
         Self {
+            diffusion: tuning
+                .input_diffusion
+                .iter()
+                .map(|t| Allpass::new((t * samplerate) as _))
+                .collect(),
             tank: Feedback::new(
                 Sequence::new(
-                    Allpass::new((0.1 * samplerate) as _),
-                    Allpass::new((0.35 * samplerate) as _),
+                    Allpass::with_mixer((tuning.tank[0] * samplerate) as _, mixer()),
+                    Allpass::with_mixer((tuning.tank[1] * samplerate) as _, mixer()),
+                ),
+                Sequence::new(
+                    Parallel::new(|i| {
+                        let phase = i as f32 / N as f32;
+                        ModulatedDelay::new(samplerate, MAX_TANK_MOD_DEPTH, phase, i as u32)
+                    }),
+                    Parallel::new(|_| DampedGain::new(0.4)),
                 ),
-                Parallel::new(|_| Gain { gain: 0.4 }),
             ),
             modulation: DryWet::new(Parallel::new(|i| {
-                let mut c = Chorus::new((samplerate * 0.5) as _);
+                let mut c = Chorus::new(samplerate, MAX_CHORUS_DEPTH);
                 c.set_pos(i as f32 / N as f32);
                 c
             })),
@@ -43,10 +67,30 @@ impl<const N: usize> ReverbTail<N> {
     }
 
     pub fn update_feedback(&mut self, feedback: f32) {
-        self.tank.backward_mut().update(|g| g.gain = feedback);
+        self.tank.backward_mut().pb.update(|g| g.gain = feedback);
+    }
+
+    pub fn update_damping(&mut self, samplerate: f32, cutoff: f32) {
+        self.tank
+            .backward_mut()
+            .pb
+            .update(|g| g.damping.set_cutoff(samplerate, cutoff));
+    }
+
+    /// Sets the shape, rate (in Hz) and depth (in seconds) of the delay modulation inside the
+    /// feedback loop.
+    pub fn update_tank_modulation(&mut self, shape: ModShape, rate: f32, depth: f32) {
+        self.tank.backward_mut().pa.update(|d| {
+            d.shape = shape;
+            d.set_rate(rate);
+            d.depth = depth.max(0.0).min(MAX_TANK_MOD_DEPTH);
+        });
     }
 
     pub fn update_size(&mut self, size: f32) {
+        for d in &mut self.diffusion {
+            d.update(size);
+        }
         let seq = self.tank.forward_mut();
         seq.pa.update(size);
         seq.pb.update(size);
@@ -61,7 +105,7 @@ impl<const N: usize> ReverbTail<N> {
     }
 }
 
-impl<const N: usize> Process for ReverbTail<N> {
+impl<const N: usize, M: Process<T = f32>> Process for ReverbTail<N, M> {
     type T = f32;
     const NIN: usize = N;
     const NOUT: usize = N;
@@ -72,8 +116,20 @@ impl<const N: usize> Process for ReverbTail<N> {
         input_frame: &[Self::T],
         output_frame: &mut [Self::T],
     ) {
+        let mut diffused = [0.0; N];
         let mut tmp_frame = [0.0; N];
-        self.tank.process(ctx, input_frame, &mut tmp_frame);
+        diffused.copy_from_slice(input_frame);
+        for d in &mut self.diffusion {
+            d.process(ctx, &diffused, &mut tmp_frame);
+            diffused = tmp_frame;
+        }
+        self.tank.process(ctx, &diffused, &mut tmp_frame);
         self.modulation.process(ctx, &tmp_frame, output_frame);
     }
+
+    fn reset(&mut self) {
+        self.diffusion.iter_mut().for_each(|d| d.reset());
+        self.tank.reset();
+        self.modulation.reset();
+    }
 }
@@ -0,0 +1,162 @@
+use crate::components::{ambisonics, SPEED_OF_SOUND};
+
+/// Shoebox room for the image-source early reflection model. Coordinates are in meters, with X
+/// going from left to right, Y from back to front (the listener faces +Y), and Z upwards.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Room {
+    pub dimensions: [f32; 3],
+    pub source: [f32; 3],
+    pub listener: [f32; 3],
+    /// Absorption coefficients of the surfaces, in order: left, right, back, front, floor,
+    /// ceiling.
+    pub absorption: [f32; 6],
+}
+
+/// A single reflection as heard from the listener position.
+#[derive(Copy, Clone, Debug)]
+pub struct Reflection {
+    /// Delay after the direct sound, in seconds
+    pub delay: f32,
+    pub gain: f32,
+    /// Cutoff of the lowpass modelling the extra high frequency absorption at each bounce
+    pub cutoff: f32,
+    /// Line of the reverb (see `ambisonics::cube_directions`) closest to the direction of arrival
+    pub line: usize,
+}
+
+impl Room {
+    /// Builds a room from plugin parameters, with horizontal positions given relative to the
+    /// room dimensions, and fixed source and listener heights.
+    pub fn from_params(
+        dimensions: [f32; 3],
+        source: [f32; 2],
+        listener: [f32; 2],
+        walls: f32,
+        floor: f32,
+        ceiling: f32,
+    ) -> Self {
+        let place = |rel: f32, size: f32| (rel * size).max(0.1).min(size - 0.1);
+        Self {
+            dimensions,
+            source: [
+                place(source[0], dimensions[0]),
+                place(source[1], dimensions[1]),
+                1.5f32.min(dimensions[2] - 0.1),
+            ],
+            listener: [
+                place(listener[0], dimensions[0]),
+                place(listener[1], dimensions[1]),
+                1.2f32.min(dimensions[2] - 0.1),
+            ],
+            absorption: [walls, walls, walls, walls, floor, ceiling],
+        }
+    }
+
+    /// Computes the reflections of the room up to `max_order` bounces, replacing the contents of
+    /// `out`. The direct sound is not included, and delays and gains are relative to it.
+    pub fn reflections(&self, max_order: usize, out: &mut Vec<Reflection>) {
+        out.clear();
+        let direct = distance(&self.source, &self.listener).max(0.1);
+        let reflectance = self.absorption.map(|a| (1.0 - a.max(0.0).min(1.0)).sqrt());
+        let mean_absorption = self.absorption.iter().sum::<f32>() / 6.0;
+        let lines = ambisonics::cube_directions().map(|(az, el)| {
+            [-el.cos() * az.sin(), el.cos() * az.cos(), el.sin()]
+        });
+
+        let n = max_order as i32;
+        for nx in -n..=n {
+            for ny in -n..=n {
+                for nz in -n..=n {
+                    for p in 0..8 {
+                        let index = [nx, ny, nz];
+                        let parity = [p & 1, (p >> 1) & 1, (p >> 2) & 1];
+                        let mut image = [0.0; 3];
+                        let mut gain = 1.0;
+                        let mut order = 0;
+                        for axis in 0..3 {
+                            let (n, p) = (index[axis], parity[axis]);
+                            image[axis] = (1 - 2 * p) as f32 * self.source[axis]
+                                + 2.0 * n as f32 * self.dimensions[axis];
+                            let (low, high) = ((n - p).abs(), n.abs());
+                            gain *= reflectance[2 * axis].powi(low)
+                                * reflectance[2 * axis + 1].powi(high);
+                            order += (low + high) as usize;
+                        }
+                        if order == 0 || order > max_order {
+                            continue;
+                        }
+
+                        let d = distance(&image, &self.listener);
+                        let dir = [
+                            (image[0] - self.listener[0]) / d,
+                            (image[1] - self.listener[1]) / d,
+                            (image[2] - self.listener[2]) / d,
+                        ];
+                        let dots = lines.map(|l| l[0] * dir[0] + l[1] * dir[1] + l[2] * dir[2]);
+                        let best = dots.iter().cloned().fold(f32::MIN, f32::max);
+                        // Reflections right in between lines (e.g. from the front wall) are
+                        // alternated between the closest lines to avoid biasing one side
+                        let ties = dots.iter().filter(|&&d| d >= best - 1e-4).count();
+                        let pick = (nx + ny + nz + p).rem_euclid(ties as i32) as usize;
+                        let line = dots
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, &d)| d >= best - 1e-4)
+                            .nth(pick)
+                            .unwrap()
+                            .0;
+                        out.push(Reflection {
+                            delay: (d - direct).max(0.0) / SPEED_OF_SOUND,
+                            gain: gain * direct / d,
+                            cutoff: 20000.0 * (1.0 - mean_absorption).powi(order as i32),
+                            line,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn distance(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f32>()
+        .sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room() -> Room {
+        Room {
+            dimensions: [6.0, 8.0, 3.0],
+            source: [3.0, 6.0, 1.2],
+            listener: [3.0, 3.0, 1.7],
+            absorption: [0.2; 6],
+        }
+    }
+
+    #[test]
+    fn test_first_order_count() {
+        let mut out = Vec::new();
+        room().reflections(1, &mut out);
+        assert_eq!(out.len(), 6);
+    }
+
+    #[test]
+    fn test_side_walls_are_on_opposite_sides() {
+        let mut out = Vec::new();
+        room().reflections(1, &mut out);
+        // The listener is centered between the side walls, so both reflections arrive at the same
+        // time, one on a left line and the other on a right line
+        let lateral: Vec<_> = out
+            .iter()
+            .filter(|r| out.iter().filter(|o| (o.delay - r.delay).abs() < 1e-6).count() == 2)
+            .collect();
+        assert_eq!(lateral.len(), 2);
+        assert_ne!(lateral[0].line % 2, lateral[1].line % 2);
+    }
+}
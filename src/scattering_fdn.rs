@@ -0,0 +1,75 @@
+use crate::components::{
+    delay::Delay, feedback::Feedback, filter::DampedGain, parallel::Parallel,
+    scattering::ScatteringMatrix, seq::Sequence, AudioContext, Process,
+};
+
+/// Delays of the main lines at the largest size, in seconds, repeated over the lines when there
+/// are more than eight
+const LINES: [f32; 8] = [
+    0.0862, 0.1078, 0.1234, 0.1406, 0.1582, 0.1794, 0.1946, 0.2238,
+];
+/// Number of delay stages inside the scattering matrix
+const STAGES: usize = 3;
+/// Longest delay inside the scattering matrix, in seconds
+const SCATTERING_DELAY: f32 = 0.002;
+/// Seed of the scattering matrix
+const SEED: u32 = 0x5CA7;
+
+/// Backward path of the tank: the scattering matrix, then the damped feedback gains
+type TankReturn<const N: usize> = Sequence<f32, ScatteringMatrix<N>, Parallel<DampedGain<f32>, N>>;
+
+/// Feedback delay network whose feedback matrix is a `ScatteringMatrix`: every round trip through
+/// the network scatters each echo into many, so the tail gets dense without extra delay lines.
+pub(crate) struct ScatteringFdn<const N: usize> {
+    tank: Feedback<Parallel<Delay<f32>, N>, TankReturn<N>, N>,
+    sample_rate: f32,
+}
+
+impl<const N: usize> ScatteringFdn<N> {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            tank: Feedback::new(
+                Parallel::new(|i| {
+                    let delay = LINES[i % LINES.len()] * (1.0 + 0.05 * (i / LINES.len()) as f32);
+                    Delay::new((delay * sample_rate) as _)
+                }),
+                Sequence::new(
+                    ScatteringMatrix::new(STAGES, (SCATTERING_DELAY * sample_rate) as _, SEED),
+                    Parallel::new(|_| DampedGain::new(0.4)),
+                ),
+            ),
+            sample_rate,
+        }
+    }
+
+    pub fn update_size(&mut self, size: f32) {
+        let pos = size.max(0.05).min(1.0);
+        self.tank.forward_mut().update(|d| d.pos = pos);
+    }
+
+    pub fn update_feedback(&mut self, feedback: f32) {
+        self.tank.backward_mut().pb.update(|g| g.gain = feedback);
+    }
+
+    pub fn update_damping(&mut self, cutoff: f32) {
+        let sample_rate = self.sample_rate;
+        self.tank
+            .backward_mut()
+            .pb
+            .update(|g| g.damping.set_cutoff(sample_rate, cutoff));
+    }
+}
+
+impl<const N: usize> Process for ScatteringFdn<N> {
+    type T = f32;
+    const NIN: usize = N;
+    const NOUT: usize = N;
+
+    fn process(&mut self, ctx: &AudioContext, input_frame: &[f32], output_frame: &mut [f32]) {
+        self.tank.process(ctx, input_frame, output_frame);
+    }
+
+    fn reset(&mut self) {
+        self.tank.reset();
+    }
+}
@@ -0,0 +1,146 @@
+use crate::components::{
+    delay::DelayLine, filter::OnePole, schroeder::SchroederAllpass, AudioContext, Process,
+    SingleChannelProcess,
+};
+use crate::layout::StereoLines;
+
+/// Number of first-order allpasses in the dispersion chain of each spring
+const STAGES: usize = 80;
+/// Round trip times of the springs at the smallest and largest sizes, in seconds
+const LENGTH: [f32; 2] = [0.03, 0.1];
+/// Length differences between the left and right springs
+const DETUNE: [f32; 2] = [1.0, 1.13];
+/// Upper limit of the bandwidth of the springs and transducers, in Hz
+const BANDWIDTH: f32 = 4500.0;
+
+/// A single spring: a dispersive chain of first-order allpasses inside a damped feedback delay,
+/// so that each round trip smears the echo further into a chirp.
+struct Spring {
+    dispersion: Vec<SchroederAllpass<f32>>,
+    delay: DelayLine<f32>,
+    damping: OnePole<f32>,
+    bandwidth: OnePole<f32>,
+    length: f32,
+    last: f32,
+}
+
+impl Spring {
+    fn new(sample_rate: f32, detune: f32) -> Self {
+        Self {
+            dispersion: (0..STAGES)
+                .map(|_| SchroederAllpass::new(2, 1.0, 0.0))
+                .collect(),
+            delay: DelayLine::new((LENGTH[1] * detune * sample_rate).ceil() as usize + 1),
+            damping: OnePole::bypass(),
+            bandwidth: OnePole::new(sample_rate, BANDWIDTH),
+            length: LENGTH[0] * detune * sample_rate,
+            last: 0.0,
+        }
+    }
+
+    fn process(&mut self, ctx: &AudioContext, value: f32, decay: f32) -> f32 {
+        let damped = self.damping.process_single_channel(ctx, self.last);
+        let mut x = value + decay * damped;
+        for stage in &mut self.dispersion {
+            x = stage.process_single_channel(ctx, x);
+        }
+        self.delay.push_pop(x);
+        self.last = self.delay.read(self.length);
+        self.bandwidth.process_single_channel(ctx, x)
+    }
+
+    fn reset(&mut self) {
+        self.dispersion
+            .iter_mut()
+            .for_each(|s| s.reset_single_channel());
+        self.delay.clear();
+        self.damping.reset_single_channel();
+        self.bandwidth.reset_single_channel();
+        self.last = 0.0;
+    }
+}
+
+/// Spring reverb after the dispersive allpass models of Välimäki, Parker and Abel: a pair of
+/// springs of slightly different lengths, one per side, each a long chain of first-order allpasses
+/// in a feedback delay. The allpasses delay low frequencies more than high ones, giving the
+/// characteristic "drip" chirp, which gets more pronounced with the tension.
+///
+/// The lines come in and go out through `StereoLines`, each spring taking one side.
+pub(crate) struct SpringReverb<const N: usize> {
+    sample_rate: f32,
+    lines: StereoLines<N>,
+    springs: [Spring; 2],
+    decay: f32,
+    tension: f32,
+}
+
+impl<const N: usize> SpringReverb<N> {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut spring = Self {
+            sample_rate,
+            lines: StereoLines::new(),
+            springs: DETUNE.map(|d| Spring::new(sample_rate, d)),
+            decay: 0.5,
+            tension: -1.0,
+        };
+        spring.update_tension(0.5);
+        spring
+    }
+
+    /// Sets the length of the springs, from shortest at 0 to longest at 1.
+    pub fn update_size(&mut self, size: f32) {
+        let size = size.max(0.0).min(1.0);
+        let length = LENGTH[0] + (LENGTH[1] - LENGTH[0]) * size;
+        for (spring, detune) in self.springs.iter_mut().zip(DETUNE.iter()) {
+            spring.length = length * detune * self.sample_rate;
+        }
+    }
+
+    pub fn update_feedback(&mut self, feedback: f32) {
+        self.decay = feedback.max(0.0).min(1.0);
+    }
+
+    pub fn update_damping(&mut self, cutoff: f32) {
+        for spring in &mut self.springs {
+            spring.damping.set_cutoff(self.sample_rate, cutoff);
+        }
+    }
+
+    /// Follows the decorrelation of the plugin mixers, see `StereoLines`.
+    pub fn update_decorrelation(&mut self, decorrelation: f32) {
+        self.lines.set_decorrelation(decorrelation);
+    }
+
+    /// Sets the amount of dispersion of the springs, between 0 and 1.
+    pub fn update_tension(&mut self, tension: f32) {
+        if tension == self.tension {
+            return;
+        }
+        self.tension = tension;
+        let gain = 0.3 + 0.45 * tension.max(0.0).min(1.0);
+        for spring in &mut self.springs {
+            for stage in &mut spring.dispersion {
+                stage.gain = gain;
+            }
+        }
+    }
+}
+
+impl<const N: usize> Process for SpringReverb<N> {
+    type T = f32;
+    const NIN: usize = N;
+    const NOUT: usize = N;
+
+    fn process(&mut self, ctx: &AudioContext, input_frame: &[f32], output_frame: &mut [f32]) {
+        let mut stereo = self.lines.downmix(ctx, input_frame);
+        let decay = self.decay;
+        for (x, spring) in stereo.iter_mut().zip(self.springs.iter_mut()) {
+            *x = spring.process(ctx, *x, decay);
+        }
+        self.lines.upmix(ctx, &stereo, output_frame);
+    }
+
+    fn reset(&mut self) {
+        self.springs.iter_mut().for_each(|s| s.reset());
+    }
+}